@@ -0,0 +1,99 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Type};
+
+/// Derives `cfr_proto::Point` and `cfr_proto::StaticPoint` for a `#[repr(C)]` struct
+/// with named `f64` fields. Field names become feature names.
+#[proc_macro_derive(Point)]
+pub fn derive_point(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "Point cannot be derived for generic structs",
+        ));
+    }
+    if !has_repr_c(input) {
+        return Err(syn::Error::new_spanned(
+            name,
+            "Point requires #[repr(C)] so that fields are laid out as a [f64] slice",
+        ));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "Point requires a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "Point can only be derived for structs",
+            ))
+        }
+    };
+    for field in fields {
+        if !is_f64(&field.ty) {
+            return Err(syn::Error::new_spanned(
+                &field.ty,
+                "all fields of a Point must be f64",
+            ));
+        }
+    }
+    let idents: Vec<_> = fields.iter().map(|f| f.ident.clone().unwrap()).collect();
+    let names: Vec<String> = idents.iter().map(|i| i.to_string()).collect();
+    let n_features: usize = idents.len();
+    let indices = 0..n_features;
+    Ok(quote! {
+        const _: () = assert!(
+            ::std::mem::size_of::<#name>() == #n_features * ::std::mem::size_of::<f64>()
+        );
+
+        impl ::cfr_proto::StaticPoint for #name {
+            fn feature_names() -> &'static [&'static str] {
+                &[#(#names),*]
+            }
+        }
+
+        impl ::cfr_proto::Point for #name {
+            fn as_slice(&self) -> &[f64] {
+                // SAFETY: `#[repr(C)]` struct of f64 fields only, its size is checked above
+                unsafe { ::std::slice::from_raw_parts(self as *const Self as *const f64, #n_features) }
+            }
+
+            fn from_slice(values: &[f64]) -> Self {
+                assert_eq!(values.len(), #n_features, "wrong number of features");
+                Self { #(#idents: values[#indices]),* }
+            }
+        }
+    })
+}
+
+fn has_repr_c(input: &DeriveInput) -> bool {
+    input.attrs.iter().any(|attr| {
+        let mut is_c = false;
+        if attr.path().is_ident("repr") {
+            let _ = attr.parse_nested_meta(|meta| {
+                is_c |= meta.path.is_ident("C");
+                Ok(())
+            });
+        }
+        is_c
+    })
+}
+
+fn is_f64(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("f64"))
+}
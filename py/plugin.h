@@ -6,14 +6,26 @@ struct Array2Ptr {
     int dim2;
 };
 
-uint64_t new_model();
+/* Functions returning int report 0 on success and non-zero if a Python
+   exception was raised; the formatted traceback is then available through
+   get_last_error. */
+
+/* spec is a JSON object {"estimator": "<module>.<Class>", "params": {...}} */
+int new_model(const char* spec, uint64_t* model_key);
 
 void delete_model(uint64_t model_key);
 
-void fit(uint64_t model_key, struct Array2Ptr x, struct Array2Ptr y);
+int fit(uint64_t model_key, struct Array2Ptr x, struct Array2Ptr y);
+
+int predict(struct Array2Ptr output, uint64_t model_key, struct Array2Ptr x);
 
-void predict(struct Array2Ptr output, uint64_t model_key, struct Array2Ptr x);
+/* Writes the serialized params length in bytes into *length and, if
+   capacity is greater than that length, copies the nul-terminated params
+   into output. Call with capacity 0 to query the size first. */
+int get_params(uint64_t model_key, char* output, uint64_t capacity, uint64_t* length);
 
-void get_params(uint64_t model_key, char* output);
+int set_params(uint64_t model_key, const char* params);
 
-void set_params(uint64_t model_key, const char* params);
+/* Copies at most capacity - 1 bytes of the last error message into output
+   (nul-terminated) and returns the full message length in bytes. */
+int get_last_error(char* output, int capacity);
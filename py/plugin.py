@@ -16,14 +16,27 @@ ffibuilder.set_source("pyemb_plugin", """
 
 ffibuilder.embedding_init_code(f"""
     import sys
+    import threading
+    import traceback
     sys.path.insert(0, "{py_root}")
     from pyemb_plugin import ffi
-    
+
+    # per thread, as each calling Rust thread reads the error of its own failed call
+    _errors = threading.local()
+
+    def _set_last_error():
+        _errors.last = traceback.format_exc()
+        return 1
+
     @ffi.def_extern()
-    def new_model() -> int:
-        import src
-        model_key: int = src.new_model()
-        return model_key
+    def new_model(spec: ffi.CData, model_key: ffi.CData) -> int:
+        try:
+            import src
+            spec = ffi.string(spec).decode()
+            model_key[0] = src.new_model(spec)
+            return 0
+        except Exception:
+            return _set_last_error()
     
     @ffi.def_extern()
     def delete_model(model_key: int):
@@ -31,31 +44,57 @@ ffibuilder.embedding_init_code(f"""
         src.delete_model(model_key)
 
     @ffi.def_extern()
-    def fit(model_key: int, x: ffi.CData, y: ffi.CData):
-        import src
-        x = src.ndarray_from_array2(x)
-        y = src.ndarray_from_array2(y)
-        src.fit(model_key, x, y)
+    def fit(model_key: int, x: ffi.CData, y: ffi.CData) -> int:
+        try:
+            import src
+            x = src.ndarray_from_array2(x)
+            y = src.ndarray_from_array2(y)
+            src.fit(model_key, x, y)
+            return 0
+        except Exception:
+            return _set_last_error()
         
     @ffi.def_extern()
-    def predict(output: ffi.CData, model_key: int, x: ffi.CData):
-        import src
-        x = src.ndarray_from_array2(x)
-        output = src.ndarray_from_array2(output)
-        prediction = src.predict(model_key, x)
-        output[:, :] = prediction.reshape(-1, 1)
+    def predict(output: ffi.CData, model_key: int, x: ffi.CData) -> int:
+        try:
+            import src
+            x = src.ndarray_from_array2(x)
+            output = src.ndarray_from_array2(output)
+            prediction = src.predict(model_key, x)
+            output[:, :] = prediction.reshape(-1, 1)
+            return 0
+        except Exception:
+            return _set_last_error()
     
     @ffi.def_extern()
-    def get_params(model_key: int, output: ffi.CData):
-        import src
-        output2 = src.get_params(model_key).encode()
-        output[0:len(output2)] = output2
+    def get_params(model_key: int, output: ffi.CData, capacity: int, length: ffi.CData) -> int:
+        try:
+            import src
+            params = src.get_params(model_key).encode()
+            length[0] = len(params)
+            if capacity > len(params):
+                ffi.memmove(output, params + b"\\0", len(params) + 1)
+            return 0
+        except Exception:
+            return _set_last_error()
     
     @ffi.def_extern()
-    def set_params(model_key: int, params: str):
-        import src
-        params = ffi.string(params).decode()
-        return src.set_params(model_key, params)
+    def set_params(model_key: int, params: str) -> int:
+        try:
+            import src
+            params = ffi.string(params).decode()
+            src.set_params(model_key, params)
+            return 0
+        except Exception:
+            return _set_last_error()
+
+    @ffi.def_extern()
+    def get_last_error(output: ffi.CData, capacity: int) -> int:
+        message = getattr(_errors, "last", "").encode()
+        if capacity > 0:
+            n = min(len(message), capacity - 1)
+            ffi.memmove(output, message[:n] + b"\\0", n + 1)
+        return len(message)
 """)
 
 ffibuilder.compile(target="libpyemb.*", verbose=True)
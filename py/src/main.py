@@ -1,8 +1,9 @@
 import numpy as np
+import importlib
 import json
+import warnings
 
-from sklearn.linear_model import SGDRegressor, LinearRegression
-
+from .serialization import decode, encode, library
 from .utils import ndarray_from_address
 
 
@@ -16,10 +17,12 @@ def ndarray_from_array2(arr):
 _models = {}
 
 
-def new_model():
+def new_model(spec: str):
     global _models
-    model = LinearRegression()
-    # model = SGDRegressor(max_iter=1_000)
+    spec = json.loads(spec)
+    module_name, class_name = spec["estimator"].rsplit(".", 1)
+    estimator_class = getattr(importlib.import_module(module_name), class_name)
+    model = estimator_class(**spec.get("params", {}))
     key = id(model)
     _models[key] = model
     return key
@@ -36,6 +39,8 @@ def fit(model_key, x, y):
     model = _models[model_key]
     y = y.reshape(-1)
     model.fit(x, y)
+    # kept on the estimator so that it is saved with the fitted attributes
+    model.training_loss_ = float(np.mean((model.predict(x) - y) ** 2))
 
 
 def predict(model_key, x):
@@ -45,23 +50,66 @@ def predict(model_key, x):
     return prediction
 
 
+# identity-link linear models, whose prediction is `x @ coef_ + intercept_` and is done
+# in Rust; GLMs such as PoissonRegressor also have `coef_` but are predicted here
+_LINEAR_MODELS = {
+    "ARDRegression",
+    "BayesianRidge",
+    "ElasticNet",
+    "ElasticNetCV",
+    "HuberRegressor",
+    "Lars",
+    "LarsCV",
+    "Lasso",
+    "LassoCV",
+    "LassoLars",
+    "LassoLarsCV",
+    "LassoLarsIC",
+    "LinearRegression",
+    "OrthogonalMatchingPursuit",
+    "OrthogonalMatchingPursuitCV",
+    "PassiveAggressiveRegressor",
+    "QuantileRegressor",
+    "Ridge",
+    "RidgeCV",
+    "SGDRegressor",
+    "TheilSenRegressor",
+}
+
+
+def _is_linear(model) -> bool:
+    cls = type(model)
+    return cls.__module__.startswith("sklearn.linear_model") and cls.__name__ in _LINEAR_MODELS
+
+
+def _library_version(model) -> str:
+    name = library(model)
+    version = getattr(importlib.import_module(name), "__version__", "unknown")
+    return f"{name} {version}"
+
+
 def get_params(model_key: int) -> str:
     global _models
     model = _models[model_key]
     if not hasattr(model, "coef_") or model.coef_ is None:
         coef_ = []
     else:
-        coef_ = model.coef_.tolist()
+        coef_ = np.ravel(model.coef_).tolist()
     if not hasattr(model, "intercept_") or model.intercept_ is None:
         intercept_ = 0
     elif isinstance(model.intercept_, np.ndarray):
         intercept_ = model.intercept_[0]
     else:
         intercept_ = model.intercept_
+    loss = getattr(model, "training_loss_", None)
     params = {
         "coef": coef_,
-        "intercept": intercept_,
-        "loss": None,
+        "intercept": float(intercept_),
+        "linear": _is_linear(model),
+        "loss": loss if loss is not None and np.isfinite(loss) else None,
+        "library_version": _library_version(model),
+        # the whole fitted estimator, nested estimators and trees included
+        "state": encode(model, {"sklearn", library(model)}),
     }
     s = json.dumps(params)
     return s
@@ -69,7 +117,23 @@ def get_params(model_key: int) -> str:
 
 def set_params(model_key: int, params: str):
     global _models
-    model = _models[model_key]
     params = json.loads(params)
-    model.coef_ = np.array(params["coef"], dtype=np.float64)
-    model.intercept_ = params["intercept"]
+    model = _models[model_key]
+    if "state" not in params:
+        # older files: only the linear coefficients are restored, a pickled estimator
+        # stored next to them is never loaded
+        model.coef_ = np.array(params["coef"], dtype=np.float64)
+        model.intercept_ = params["intercept"]
+        return
+    version = _library_version(model)
+    if params.get("library_version") != version:
+        warnings.warn(
+            f"model params saved with {params.get('library_version')}, loaded with {version}"
+        )
+    restored = decode(params["state"], {"sklearn", library(model)})
+    if type(restored) is not type(model):
+        raise ValueError(
+            f"model params of {type(restored).__name__} do not match the configured "
+            f"{type(model).__name__}"
+        )
+    _models[model_key] = restored
@@ -0,0 +1,183 @@
+"""Plain JSON encoding of fitted estimators, in place of pickles.
+
+Values are encoded recursively: JSON scalars as they are, containers and arrays
+tagged with their kind, and objects by their class and state. Only objects of the
+allowed libraries are rebuilt on decoding, so that a model file cannot make the
+loader call arbitrary code.
+"""
+import importlib
+import math
+
+import numpy as np
+
+
+def _class_path(cls) -> str:
+    return f"{cls.__module__}.{cls.__qualname__}"
+
+
+def _import_class(path: str, allowed: set):
+    module_name, _, name = path.rpartition(".")
+    if module_name.split(".")[0] not in allowed:
+        raise ValueError(f"class {path} is not of an allowed library {sorted(allowed)}")
+    value = importlib.import_module(module_name)
+    for part in name.split("."):
+        value = getattr(value, part)
+    if not isinstance(value, type):
+        raise ValueError(f"{path} is not a class")
+    return value
+
+
+def encode(value, allowed: set, name: str = "estimator"):
+    """JSON value of `value`, whose objects must be of the `allowed` libraries;
+    `name` locates the value in errors."""
+    if value is None or isinstance(value, (bool, int, str)):
+        return value
+    if isinstance(value, float):
+        # non-finite floats are not JSON, e.g. for the Rust side parsing the params
+        return value if math.isfinite(value) else {"float": repr(value)}
+    if isinstance(value, np.generic):
+        return encode(value.item(), allowed, name)
+    if isinstance(value, list):
+        return [encode(v, allowed, f"{name}[{i}]") for i, v in enumerate(value)]
+    if isinstance(value, tuple):
+        return {"tuple": encode(list(value), allowed, name)}
+    if isinstance(value, dict):
+        return {
+            "dict": [
+                [encode(k, allowed, name), encode(v, allowed, f"{name}[{k!r}]")]
+                for k, v in value.items()
+            ]
+        }
+    if isinstance(value, np.ndarray):
+        return _encode_array(value, allowed, name)
+    if isinstance(value, np.dtype):
+        return {"np_dtype": value.str}
+    if isinstance(value, np.random.RandomState):
+        return {"random_state": encode(value.get_state(), allowed, name)}
+    if isinstance(value, type):
+        return {"class": _class_path(value)}
+    return _encode_object(value, allowed, name)
+
+
+def _encode_array(value: np.ndarray, allowed: set, name: str):
+    shape = list(value.shape)
+    if value.dtype.names is not None:
+        # structured arrays, e.g. the nodes of sklearn trees
+        fields = [(field, value.dtype.fields[field]) for field in value.dtype.names]
+        return {
+            "ndarray": [
+                encode(list(row), allowed, name) for row in value.reshape(-1).tolist()
+            ],
+            "fields": {
+                "names": [field for field, _ in fields],
+                "formats": [dtype.str for _, (dtype, *_) in fields],
+                "offsets": [offset for _, (_, offset, *_) in fields],
+                "itemsize": value.dtype.itemsize,
+            },
+            "shape": shape,
+        }
+    if value.dtype == object:
+        items = value.reshape(-1).tolist()
+        return {
+            "ndarray": [encode(v, allowed, f"{name}[{i}]") for i, v in enumerate(items)],
+            "dtype": "object",
+            "shape": shape,
+        }
+    return {
+        "ndarray": encode(value.reshape(-1).tolist(), allowed, name),
+        "dtype": value.dtype.str,
+        "shape": shape,
+    }
+
+
+def _encode_object(value, allowed: set, name: str):
+    cls = type(value)
+    if cls.__module__.split(".")[0] not in allowed:
+        raise TypeError(f"{name} of type {_class_path(cls)} is not of an allowed library")
+    reduced = value.__reduce_ex__(2)
+    constructor, args = reduced[0], reduced[1]
+    state = reduced[2] if len(reduced) > 2 else None
+    encoded = {"object": _class_path(cls)}
+    if constructor is cls:
+        # extension types rebuilt from constructor arguments, e.g. sklearn's `Tree`
+        encoded["args"] = encode(args, allowed, name)
+    elif getattr(constructor, "__name__", "").startswith("__pyx_unpickle_"):
+        # Cython classes pickled by their checksum and state, e.g. sklearn's loss functions
+        encoded["cython"] = encode(args[1:], allowed, name)
+    elif getattr(constructor, "__name__", "") not in ("__newobj__", "_reconstructor"):
+        raise TypeError(f"{name} of type {_class_path(cls)} cannot be saved as plain params")
+    encoded["state"] = encode(state, allowed, name)
+    return encoded
+
+
+def library(value) -> str:
+    """Top-level package of the class of `value`, e.g. `sklearn`."""
+    return type(value).__module__.split(".")[0]
+
+
+def decode(value, allowed: set):
+    """Value encoded by `encode`, rebuilding only classes of the `allowed` libraries."""
+    if isinstance(value, list):
+        return [decode(v, allowed) for v in value]
+    if not isinstance(value, dict):
+        return value
+    if "float" in value:
+        return float(value["float"])
+    if "tuple" in value:
+        return tuple(decode(v, allowed) for v in value["tuple"])
+    if "dict" in value:
+        return {decode(k, allowed): decode(v, allowed) for k, v in value["dict"]}
+    if "ndarray" in value:
+        return _decode_array(value, allowed)
+    if "np_dtype" in value:
+        return np.dtype(value["np_dtype"])
+    if "random_state" in value:
+        random_state = np.random.RandomState()
+        random_state.set_state(decode(value["random_state"], allowed))
+        return random_state
+    if "class" in value:
+        return _import_class(value["class"], allowed | {"numpy", "builtins"})
+    if "object" in value:
+        return _decode_object(value, allowed)
+    raise ValueError(f"unknown encoded value with keys {sorted(value)}")
+
+
+def _decode_array(value, allowed: set) -> np.ndarray:
+    shape = tuple(value["shape"])
+    if "fields" in value:
+        dtype = np.dtype(value["fields"])
+        rows = [tuple(decode(row, allowed)) for row in value["ndarray"]]
+        return np.array(rows, dtype=dtype).reshape(shape)
+    if value["dtype"] == "object":
+        array = np.empty(len(value["ndarray"]), dtype=object)
+        for i, item in enumerate(value["ndarray"]):
+            array[i] = decode(item, allowed)
+        return array.reshape(shape)
+    array = np.array(decode(value["ndarray"], allowed), dtype=np.dtype(value["dtype"]))
+    return array.reshape(shape)
+
+
+def _decode_object(value, allowed: set):
+    cls = _import_class(value["object"], allowed)
+    if "cython" in value:
+        module = importlib.import_module(cls.__module__)
+        unpickle = getattr(module, f"__pyx_unpickle_{cls.__name__}")
+        obj = unpickle(cls, *decode(value["cython"], allowed))
+    elif "args" in value:
+        obj = cls(*decode(value["args"], allowed))
+    else:
+        obj = cls.__new__(cls)
+    state = decode(value["state"], allowed)
+    if state is None:
+        return obj
+    if hasattr(obj, "__setstate__"):
+        obj.__setstate__(state)
+        return obj
+    # default pickling: the instance dict, with slot values next to it for slotted classes
+    slots = {}
+    if isinstance(state, tuple):
+        state, slots = state
+    obj.__dict__.update(state or {})
+    for key, slot_value in (slots or {}).items():
+        setattr(obj, key, slot_value)
+    return obj
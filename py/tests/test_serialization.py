@@ -0,0 +1,67 @@
+"""Round trips of fitted estimators through the plain JSON model params.
+
+Run from `py/` with `python3 -m unittest discover tests`.
+"""
+import json
+import unittest
+
+try:
+    import numpy as np
+    import sklearn  # noqa: F401
+except ImportError:
+    np = None
+
+if np is not None:
+    from src.main import fit, get_params, new_model, predict, set_params
+
+
+@unittest.skipIf(np is None, "numpy and scikit-learn are required")
+class RoundTrip(unittest.TestCase):
+    def round_trip(self, estimator, **params):
+        rng = np.random.default_rng(0)
+        x = rng.normal(size=(200, 4))
+        y = x @ np.array([1.0, -2.0, 0.5, 0.0]) + rng.normal(scale=0.1, size=200)
+        spec = json.dumps({"estimator": estimator, "params": params})
+        trained = new_model(spec)
+        fit(trained, x, y.reshape(-1, 1))
+        saved = get_params(trained)
+        loaded = new_model(spec)
+        set_params(loaded, saved)
+        np.testing.assert_array_equal(predict(loaded, x), predict(trained, x))
+        self.assertEqual(get_params(loaded), saved)
+
+    def test_linear(self):
+        self.round_trip("sklearn.linear_model.Ridge", alpha=0.5)
+
+    def test_tree(self):
+        self.round_trip("sklearn.tree.DecisionTreeRegressor", max_depth=4)
+
+    def test_tree_ensembles(self):
+        self.round_trip("sklearn.ensemble.RandomForestRegressor", n_estimators=5)
+        self.round_trip("sklearn.ensemble.GradientBoostingRegressor", n_estimators=5)
+        self.round_trip("sklearn.ensemble.HistGradientBoostingRegressor", max_iter=5)
+
+    def test_neural_net(self):
+        self.round_trip(
+            "sklearn.neural_network.MLPRegressor", hidden_layer_sizes=[8], max_iter=50
+        )
+
+    def test_only_identity_link_models_are_linear(self):
+        for estimator, linear in [
+            ("sklearn.linear_model.Ridge", True),
+            ("sklearn.linear_model.PoissonRegressor", False),
+            ("sklearn.tree.DecisionTreeRegressor", False),
+        ]:
+            model = new_model(json.dumps({"estimator": estimator}))
+            self.assertEqual(json.loads(get_params(model))["linear"], linear, estimator)
+
+    def test_other_library_is_refused(self):
+        model = new_model(json.dumps({"estimator": "sklearn.linear_model.Ridge"}))
+        state = {"object": "subprocess.Popen", "args": {"tuple": [["true"]]}, "state": None}
+        params = json.dumps({"library_version": "", "state": state})
+        with self.assertRaises(ValueError):
+            set_params(model, params)
+
+
+if __name__ == "__main__":
+    unittest.main()
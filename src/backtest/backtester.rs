@@ -3,7 +3,7 @@ use std::rc::Rc;
 
 use crate::backtest::position::Position;
 use crate::backtest::strategy::Strategy;
-use crate::backtest::Profit;
+use crate::backtest::{Order, Profit, Signal, Trade};
 use crate::{DatasetRef, Point};
 
 pub struct Backtester<T: Point> {
@@ -12,6 +12,8 @@ pub struct Backtester<T: Point> {
     strategy: Box<dyn Strategy<T>>,
     realized_profits: Vec<Profit>,
     traded_volume_usd: f64,
+    /// Start timestamps of futures contracts after the first
+    roll_timestamps: Vec<i64>,
 }
 
 impl<T: Point> Backtester<T> {
@@ -26,38 +28,92 @@ impl<T: Point> Backtester<T> {
             strategy,
             realized_profits: Vec::new(),
             traded_volume_usd: 0.0,
+            roll_timestamps: Vec::new(),
         }
     }
 
-    pub fn run(&mut self) {
-        for bar in self.dataset.iter() {
-            match self.strategy.trade_decision(bar) {
-                None => {}
-                Some(order) => {
-                    self.positions.borrow_mut()[order.instrument_id.index].on_order(order);
+    /// Closes positions at the last bar of each contract and reopens them at the first
+    /// bar of the next one, without consulting the strategy on either bar.
+    pub fn with_rolls(mut self, roll_timestamps: Vec<i64>) -> Self {
+        self.roll_timestamps = roll_timestamps;
+        self
+    }
+
+    /// Whether a roll happens between the bar and the next one.
+    fn is_last_bar_of_contract(&self, index: usize) -> bool {
+        match self.dataset.get(index + 1) {
+            None => false,
+            Some(next_bar) => {
+                let timestamp: i64 = self.dataset[index].timestamp;
+                self.roll_timestamps
+                    .iter()
+                    .any(|&roll| timestamp < roll && roll <= next_bar.timestamp)
+            }
+        }
+    }
+
+    pub fn run(&mut self) -> anyhow::Result<()> {
+        let dataset: DatasetRef<T> = self.dataset.clone();
+        let mut rolled_sizes: Vec<i32> = Vec::new();
+        for (index, bar) in dataset.iter().enumerate() {
+            let is_roll: bool = self.is_last_bar_of_contract(index);
+            let reopen_sizes: Vec<i32> = std::mem::take(&mut rolled_sizes);
+            if reopen_sizes.iter().any(|&size| size != 0) {
+                for (position, size) in self.positions.borrow_mut().iter_mut().zip(reopen_sizes) {
+                    if size != 0 {
+                        let instrument_id = position.instrument_id().clone();
+                        position.on_order(Order {
+                            instrument_id,
+                            size,
+                            signal: Signal::default(),
+                        });
+                    }
+                }
+            } else if !is_roll {
+                match self.strategy.trade_decision(bar)? {
+                    None => {}
+                    Some(order) => {
+                        self.positions.borrow_mut()[order.instrument_id.index].on_order(order);
+                    }
                 }
             }
             for position in self.positions.borrow_mut().iter_mut() {
-                let trade_made: bool = position.on_bar(bar);
-                if trade_made {
-                    if position.position() == 0 {
-                        match position.last_realized_profit() {
-                            None => unreachable!(),
-                            Some(profit) => {
-                                println!("Realized profit: {:?}", profit);
-                                self.realized_profits.push(profit)
-                            }
+                let mut trade_made: bool = position.on_bar(bar);
+                if is_roll {
+                    let size: i32 = position.roll(bar);
+                    trade_made |= size != 0;
+                    rolled_sizes.push(size);
+                }
+                if trade_made && position.position() == 0 {
+                    match position.last_realized_profit() {
+                        None => unreachable!(),
+                        Some(profit) => {
+                            println!("Realized profit: {:?}", profit);
+                            self.realized_profits.push(profit)
                         }
                     }
                 }
             }
         }
+        Ok(())
     }
 
     pub fn realized_profits(&self) -> &[Profit] {
         &self.realized_profits
     }
 
+    /// Closed trades of all positions, by exit time.
+    pub fn trades(&self) -> Vec<Trade> {
+        let mut trades: Vec<Trade> = self
+            .positions
+            .borrow()
+            .iter()
+            .flat_map(|p| p.trades().iter().cloned())
+            .collect();
+        trades.sort_by_key(|trade| trade.exit_timestamp);
+        trades
+    }
+
     pub fn traded_volume_usd(&self) -> f64 {
         self.positions
             .borrow()
@@ -66,3 +122,74 @@ impl<T: Point> Backtester<T> {
             .sum()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backtest::{InstrumentId, InstrumentSpec};
+    use crate::dtypes::{Bar, Price};
+    use crate::test_support::NoFeatures as P;
+    use std::sync::Arc;
+
+    /// Goes long whenever flat.
+    struct AlwaysLong {
+        positions: Rc<RefCell<Vec<Position>>>,
+    }
+
+    impl Strategy<P> for AlwaysLong {
+        fn trade_decision(&mut self, _bar: &Bar<P>) -> anyhow::Result<Option<Order>> {
+            let position = &self.positions.borrow()[0];
+            Ok((position.position() == 0).then(|| Order {
+                instrument_id: position.instrument_id().clone(),
+                size: 1,
+                signal: Signal::default(),
+            }))
+        }
+    }
+
+    #[test]
+    fn test_roll() {
+        let dataset: DatasetRef<P> = Arc::new(
+            [100.0, 101.0, 111.0, 112.0]
+                .iter()
+                .enumerate()
+                .map(|(i, &price)| Bar {
+                    timestamp: i as i64,
+                    mid_price: Price(price),
+                    point: P {},
+                })
+                .collect(),
+        );
+        let instrument_id = InstrumentId {
+            index: 0,
+            symbol: "NQ".to_string(),
+        };
+        let instrument_spec = InstrumentSpec {
+            multiplier: 20.0,
+            fee: 1.5,
+            roll_fee: 2.0,
+        };
+        let positions = Rc::new(RefCell::new(vec![Position::new(
+            instrument_id,
+            instrument_spec,
+        )]));
+        let strategy = Box::new(AlwaysLong {
+            positions: positions.clone(),
+        });
+        let mut backtester =
+            Backtester::new(positions.clone(), dataset, strategy).with_rolls(vec![2]);
+        backtester.run().unwrap();
+        // opened at 100, rolled out at 101 and back in at 111, not at 101 again
+        let profits: Vec<f64> = backtester
+            .realized_profits()
+            .iter()
+            .map(|p| p.profit)
+            .collect();
+        assert_eq!(profits, [20.0 - 3.5]);
+        let trades = backtester.trades();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].exit_reason, crate::backtest::ExitReason::Roll);
+        assert_eq!(positions.borrow()[0].position(), 1);
+        assert_eq!(positions.borrow()[0].unrealized_profit(112.0), 20.0 - 1.5);
+    }
+}
@@ -0,0 +1,176 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::backtest::Profit;
+use crate::config::BootstrapConfig;
+
+const NANOS_PER_DAY: i64 = 86_400_000_000_000;
+
+/// Monte Carlo confidence intervals of a backtest, from resampled trades and from
+/// resampled blocks of daily profits, which keep the serial dependence within a block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bootstrap {
+    pub n_samples: usize,
+    pub confidence: f64,
+    /// Trades drawn independently with replacement; Sharpe per trade
+    pub trades: BootstrapIntervals,
+    /// Blocks of `block_days` consecutive trading days drawn with replacement; Sharpe per day
+    pub daily: BootstrapIntervals,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BootstrapIntervals {
+    pub total_profit: Interval,
+    pub sharpe: Interval,
+    pub max_drawdown: Interval,
+    /// Share of resampled sequences with a positive total profit
+    pub prob_positive: f64,
+}
+
+/// Estimate of the original sequence with the bounds of the resampled ones
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interval {
+    #[serde(deserialize_with = "crate::backtest::report::null_as_nan")]
+    pub estimate: f64,
+    #[serde(deserialize_with = "crate::backtest::report::null_as_nan")]
+    pub low: f64,
+    #[serde(deserialize_with = "crate::backtest::report::null_as_nan")]
+    pub high: f64,
+}
+
+impl Bootstrap {
+    /// `None` without trades or with bootstrapping disabled.
+    pub fn new(profits: &[Profit], config: &BootstrapConfig) -> Option<Self> {
+        if profits.is_empty() || config.n_samples == 0 {
+            return None;
+        }
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let trades: Vec<f64> = profits.iter().map(|p| p.profit).collect();
+        let trades_intervals = resample(&trades, config, &mut rng, |rng, sample| {
+            sample.extend((0..trades.len()).map(|_| trades[rng.gen_range(0..trades.len())]));
+        });
+        let days: Vec<f64> = daily_profits(profits);
+        let block_days: usize = config.block_days.min(days.len());
+        let daily_intervals = resample(&days, config, &mut rng, |rng, sample| {
+            // circular blocks, so that every day is equally likely to be drawn
+            while sample.len() < days.len() {
+                let start: usize = rng.gen_range(0..days.len());
+                sample.extend((start..start + block_days).map(|i| days[i % days.len()]));
+            }
+            sample.truncate(days.len());
+        });
+        Some(Self {
+            n_samples: config.n_samples,
+            confidence: config.confidence,
+            trades: trades_intervals,
+            daily: daily_intervals,
+        })
+    }
+}
+
+/// Profits summed per UTC day with at least one closed trade, in time order.
+fn daily_profits(profits: &[Profit]) -> Vec<f64> {
+    let mut days: Vec<(i64, f64)> = Vec::new();
+    for profit in profits {
+        let day: i64 = profit.timestamp.div_euclid(NANOS_PER_DAY);
+        match days.last_mut() {
+            Some((last_day, total)) if *last_day == day => *total += profit.profit,
+            _ => days.push((day, profit.profit)),
+        }
+    }
+    days.into_iter().map(|(_, total)| total).collect()
+}
+
+/// Statistics of `config.n_samples` sequences filled by `draw`.
+fn resample(
+    values: &[f64],
+    config: &BootstrapConfig,
+    rng: &mut StdRng,
+    draw: impl Fn(&mut StdRng, &mut Vec<f64>),
+) -> BootstrapIntervals {
+    let mut totals: Vec<f64> = Vec::with_capacity(config.n_samples);
+    let mut sharpes: Vec<f64> = Vec::with_capacity(config.n_samples);
+    let mut drawdowns: Vec<f64> = Vec::with_capacity(config.n_samples);
+    let mut sample: Vec<f64> = Vec::with_capacity(values.len());
+    for _ in 0..config.n_samples {
+        sample.clear();
+        draw(rng, &mut sample);
+        let (total, sharpe, drawdown) = statistics(&sample);
+        totals.push(total);
+        sharpes.push(sharpe);
+        drawdowns.push(drawdown);
+    }
+    let prob_positive: f64 =
+        totals.iter().filter(|&&total| total > 0.0).count() as f64 / config.n_samples as f64;
+    let (total, sharpe, drawdown) = statistics(values);
+    BootstrapIntervals {
+        total_profit: interval(total, &mut totals, config.confidence),
+        sharpe: interval(sharpe, &mut sharpes, config.confidence),
+        max_drawdown: interval(drawdown, &mut drawdowns, config.confidence),
+        prob_positive,
+    }
+}
+
+/// Total, Sharpe and max drawdown of a profit sequence.
+fn statistics(values: &[f64]) -> (f64, f64, f64) {
+    let n: f64 = values.len() as f64;
+    let total: f64 = values.iter().sum();
+    let mean: f64 = total / n;
+    let std: f64 = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+    let mut cumulative: f64 = 0.0;
+    let mut peak: f64 = 0.0;
+    let mut max_drawdown: f64 = 0.0;
+    for value in values {
+        cumulative += value;
+        peak = peak.max(cumulative);
+        max_drawdown = max_drawdown.max(peak - cumulative);
+    }
+    (total, mean / std, max_drawdown)
+}
+
+/// Equal-tailed interval of the finite samples; NaN bounds if there are none.
+fn interval(estimate: f64, samples: &mut Vec<f64>, confidence: f64) -> Interval {
+    samples.retain(|v| v.is_finite());
+    samples.sort_by(f64::total_cmp);
+    let quantile = |q: f64| -> f64 {
+        if samples.is_empty() {
+            return f64::NAN;
+        }
+        samples[((q * samples.len() as f64) as usize).min(samples.len() - 1)]
+    };
+    Interval {
+        estimate,
+        low: quantile((1.0 - confidence) / 2.0),
+        high: quantile((1.0 + confidence) / 2.0),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bootstrap() {
+        // two trades a day over 50 days, winning more than losing
+        let profits: Vec<Profit> = (0..100)
+            .map(|i| Profit {
+                timestamp: i / 2 * NANOS_PER_DAY + i % 2,
+                profit: if i % 3 == 0 { -10.0 } else { 20.0 },
+            })
+            .collect();
+        let config = BootstrapConfig::default();
+        let bootstrap = Bootstrap::new(&profits, &config).unwrap();
+        assert_eq!(daily_profits(&profits).len(), 50);
+        for intervals in [&bootstrap.trades, &bootstrap.daily] {
+            let total = &intervals.total_profit;
+            assert_eq!(total.estimate, 980.0);
+            assert!(total.low < total.estimate && total.estimate < total.high);
+            assert!(intervals.max_drawdown.low <= intervals.max_drawdown.high);
+            assert!(intervals.prob_positive > 0.99);
+        }
+        let again = Bootstrap::new(&profits, &config).unwrap();
+        assert_eq!(again.daily.sharpe.low, bootstrap.daily.sharpe.low);
+        assert!(Bootstrap::new(&[], &config).is_none());
+    }
+}
@@ -2,4 +2,6 @@
 pub struct InstrumentSpec {
     pub multiplier: f64,
     pub fee: f64,
+    /// Charged per contract on top of `fee` when a position is rolled to the next contract
+    pub roll_fee: f64,
 }
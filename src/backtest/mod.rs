@@ -1,16 +1,22 @@
 #![allow(dead_code)]
 mod backtester;
+mod bootstrap;
 mod instrument_id;
 mod instrument_spec;
 mod order;
 mod position;
 mod profit;
+mod report;
 mod strategy;
+mod trade;
 
 pub use backtester::Backtester;
+pub use bootstrap::{Bootstrap, BootstrapIntervals, Interval};
 pub use instrument_id::InstrumentId;
 pub use instrument_spec::InstrumentSpec;
-pub use order::Order;
+pub use order::{Order, Signal};
 pub use position::Position;
 pub use profit::Profit;
+pub use report::BacktestReport;
 pub use strategy::Strategy;
+pub use trade::{ExitReason, Trade, TradeSide};
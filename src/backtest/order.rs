@@ -4,4 +4,14 @@ use crate::backtest::instrument_id::InstrumentId;
 pub struct Order {
     pub instrument_id: InstrumentId,
     pub size: i32,
+    pub signal: Signal,
+}
+
+/// Model utilities a strategy based an order on, recorded with the trade it opens or closes
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Signal {
+    pub open_long_utility: Option<f64>,
+    pub open_short_utility: Option<f64>,
+    /// Utility of holding the position instead of closing it
+    pub hold_utility: Option<f64>,
 }
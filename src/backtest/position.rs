@@ -2,7 +2,8 @@ use std::collections::VecDeque;
 
 use crate::backtest::instrument_id::InstrumentId;
 use crate::backtest::instrument_spec::InstrumentSpec;
-use crate::backtest::order::Order;
+use crate::backtest::order::{Order, Signal};
+use crate::backtest::trade::{ExitReason, Trade, TradeSide};
 use crate::backtest::Profit;
 use crate::dtypes::Bar;
 use crate::Point;
@@ -16,6 +17,25 @@ pub struct Position {
     realized_profits: Vec<Profit>,
     realized_profit: f64,
     traded_volume_usd: f64,
+    /// Bars seen by `on_bar`
+    n_bars: usize,
+    open_trade: Option<OpenTrade>,
+    trades: Vec<Trade>,
+}
+
+/// Trade being built while the position is open
+struct OpenTrade {
+    side: TradeSide,
+    size: i32,
+    entry_timestamp: i64,
+    entry_bar: usize,
+    exit_size: i32,
+    exit_value: f64,
+    fees: f64,
+    profit: f64,
+    mae: f64,
+    mfe: f64,
+    entry_signal: Signal,
 }
 
 impl Position {
@@ -29,6 +49,9 @@ impl Position {
             realized_profits: Vec::new(),
             realized_profit: 0.0,
             traded_volume_usd: 0.0,
+            n_bars: 0,
+            open_trade: None,
+            trades: Vec::new(),
         }
     }
 
@@ -41,62 +64,160 @@ impl Position {
 
     /// Returns true if a trade was made.
     pub fn on_bar<T: Point>(&mut self, bar: &Bar<T>) -> bool {
-        match self.orders.pop_front() {
+        self.n_bars += 1;
+        // before the order, so that the exit price counts towards the excursions
+        self.mark(bar.mid_price.0);
+        let trade_made: bool = match self.orders.pop_front() {
             None => false,
             Some(order) => {
-                let order_size: i32 = order.size;
-                self.traded_volume_usd +=
-                    order_size.abs() as f64 * bar.mid_price.0 * self.instrument_spec.multiplier;
-                let is_closing: bool = self.position.signum() == -order_size.signum();
-                if is_closing {
-                    let abs_position_before_update: i32 = self.position.abs();
-                    let abs_execution_size: i32 = order_size.abs();
-                    let abs_closed_size: i32 = abs_position_before_update.min(abs_execution_size);
-                    let position_sign: i32 = self.position.signum();
-                    let entry_price: f64 = self.avg_price;
-                    let exit_price: f64 = bar.mid_price.0; // TODO: can be something more sofisticated
-                    let multiplier: f64 = self.instrument_spec.multiplier;
-                    let profit: f64 = multiplier
-                        * (abs_closed_size as f64)
-                        * position_sign as f64
-                        * (exit_price - entry_price)
-                        - self.instrument_spec.fee * (abs_closed_size as f64);
-                    self.realized_profit += profit;
-                    let profit_pt = Profit {
-                        timestamp: bar.timestamp,
-                        profit,
-                    };
-                    self.realized_profits.push(profit_pt);
-                    let closed_size: i32 = abs_closed_size * (-position_sign);
-                    let open_size: i32 = self.position + order_size;
-                    self.position += closed_size;
-                    if self.position == 0 {
-                        if open_size == 0 {
-                            self.avg_price = 0.0;
-                        } else {
-                            self.position += open_size;
-                            self.avg_price = exit_price;
-                        }
-                    }
+                let fee: f64 = self.instrument_spec.fee;
+                self.execute(order.size, bar, fee, order.signal, ExitReason::Order);
+                true
+            }
+        };
+        trade_made
+    }
+
+    /// Closes the position at the last bar of a futures contract, charging the roll fee
+    /// as well, and returns the size to reopen in the next contract.
+    pub fn roll<T: Point>(&mut self, bar: &Bar<T>) -> i32 {
+        let size: i32 = self.position;
+        if size != 0 {
+            let fee: f64 = self.instrument_spec.fee + self.instrument_spec.roll_fee;
+            self.execute(-size, bar, fee, Signal::default(), ExitReason::Roll);
+        }
+        size
+    }
+
+    fn execute<T: Point>(
+        &mut self,
+        order_size: i32,
+        bar: &Bar<T>,
+        fee: f64,
+        signal: Signal,
+        exit_reason: ExitReason,
+    ) {
+        self.traded_volume_usd +=
+            order_size.abs() as f64 * bar.mid_price.0 * self.instrument_spec.multiplier;
+        let is_closing: bool = self.position.signum() == -order_size.signum();
+        if is_closing {
+            let abs_position_before_update: i32 = self.position.abs();
+            let abs_execution_size: i32 = order_size.abs();
+            let abs_closed_size: i32 = abs_position_before_update.min(abs_execution_size);
+            let position_sign: i32 = self.position.signum();
+            let entry_price: f64 = self.avg_price;
+            let exit_price: f64 = bar.mid_price.0; // TODO: can be something more sofisticated
+            let multiplier: f64 = self.instrument_spec.multiplier;
+            let profit: f64 = multiplier
+                * (abs_closed_size as f64)
+                * position_sign as f64
+                * (exit_price - entry_price)
+                - fee * (abs_closed_size as f64);
+            self.realized_profit += profit;
+            let profit_pt = Profit {
+                timestamp: bar.timestamp,
+                profit,
+            };
+            self.realized_profits.push(profit_pt);
+            if let Some(trade) = self.open_trade.as_mut() {
+                trade.exit_size += abs_closed_size;
+                trade.exit_value += abs_closed_size as f64 * exit_price;
+                trade.fees += fee * abs_closed_size as f64;
+                trade.profit += profit;
+            }
+            let closed_size: i32 = abs_closed_size * (-position_sign);
+            let open_size: i32 = self.position + order_size;
+            self.position += closed_size;
+            if self.position == 0 {
+                self.close_trade(bar.timestamp, entry_price, signal, exit_reason);
+                if open_size == 0 {
+                    self.avg_price = 0.0;
                 } else {
-                    let abs_position_before_update: f64 = self.position.abs() as f64;
-                    let execution_price: f64 = bar.mid_price.0; // TODO: can be something more sofisticated
-                    let abs_execution_size: f64 = order_size.abs() as f64;
-                    let avp: f64 = self.avg_price;
-                    self.position += order_size;
-                    if self.position == 0 {
-                        self.avg_price = 0.0;
-                    } else {
-                        self.avg_price = (avp * abs_position_before_update
-                            + execution_price * abs_execution_size)
-                            / (abs_position_before_update + abs_execution_size);
-                    }
+                    self.position += open_size;
+                    self.avg_price = exit_price;
+                    self.open_trade(bar.timestamp, signal);
                 }
-                true
+            }
+        } else {
+            let abs_position_before_update: f64 = self.position.abs() as f64;
+            let execution_price: f64 = bar.mid_price.0; // TODO: can be something more sofisticated
+            let abs_execution_size: f64 = order_size.abs() as f64;
+            let avp: f64 = self.avg_price;
+            self.position += order_size;
+            match self.open_trade.as_mut() {
+                Some(trade) => trade.size = trade.size.max(self.position.abs()),
+                None if self.position != 0 => self.open_trade(bar.timestamp, signal),
+                None => {}
+            }
+            if self.position == 0 {
+                self.avg_price = 0.0;
+            } else {
+                self.avg_price = (avp * abs_position_before_update
+                    + execution_price * abs_execution_size)
+                    / (abs_position_before_update + abs_execution_size);
             }
         }
     }
 
+    fn open_trade(&mut self, timestamp: i64, signal: Signal) {
+        self.open_trade = Some(OpenTrade {
+            side: if self.position < 0 {
+                TradeSide::Short
+            } else {
+                TradeSide::Long
+            },
+            size: self.position.abs(),
+            entry_timestamp: timestamp,
+            entry_bar: self.n_bars,
+            exit_size: 0,
+            exit_value: 0.0,
+            fees: 0.0,
+            profit: 0.0,
+            mae: 0.0,
+            mfe: 0.0,
+            entry_signal: signal,
+        });
+    }
+
+    fn close_trade(
+        &mut self,
+        timestamp: i64,
+        entry_price: f64,
+        signal: Signal,
+        exit_reason: ExitReason,
+    ) {
+        if let Some(trade) = self.open_trade.take() {
+            self.trades.push(Trade {
+                instrument: self.instrument_id.symbol.clone(),
+                side: trade.side,
+                size: trade.size,
+                entry_timestamp: trade.entry_timestamp,
+                entry_price,
+                exit_timestamp: timestamp,
+                exit_price: trade.exit_value / trade.exit_size as f64,
+                fees: trade.fees,
+                profit: trade.profit,
+                bars_held: self.n_bars - trade.entry_bar,
+                mae: trade.mae,
+                mfe: trade.mfe,
+                exit_reason,
+                entry_open_long_utility: trade.entry_signal.open_long_utility,
+                entry_open_short_utility: trade.entry_signal.open_short_utility,
+                exit_hold_utility: signal.hold_utility,
+            });
+        }
+    }
+
+    /// Updates the excursions of the open trade.
+    fn mark(&mut self, mid_price: f64) {
+        let multiplier: f64 = self.instrument_spec.multiplier;
+        let unrealized: f64 = multiplier * self.position as f64 * (mid_price - self.avg_price);
+        if let Some(trade) = self.open_trade.as_mut() {
+            trade.mae = trade.mae.min(unrealized);
+            trade.mfe = trade.mfe.max(unrealized);
+        }
+    }
+
     pub fn position(&self) -> i32 {
         self.position
     }
@@ -109,6 +230,18 @@ impl Position {
         self.realized_profit
     }
 
+    /// Profit of closing the open position at `mid_price`, including the fee.
+    pub fn unrealized_profit(&self, mid_price: f64) -> f64 {
+        let multiplier: f64 = self.instrument_spec.multiplier;
+        multiplier * self.position as f64 * (mid_price - self.avg_price)
+            - self.instrument_spec.fee * self.position.abs() as f64
+    }
+
+    /// Closed trades, in the order they were closed.
+    pub fn trades(&self) -> &[Trade] {
+        &self.trades
+    }
+
     pub fn last_realized_profit(&self) -> Option<Profit> {
         self.realized_profits.last().cloned()
     }
@@ -126,10 +259,7 @@ impl Position {
 mod test {
     use super::*;
     use crate::dtypes::Price;
-
-    #[derive(Debug, Clone)]
-    struct P {}
-    impl Point for P {}
+    use crate::test_support::NoFeatures as P;
 
     #[test]
     fn test() {
@@ -157,15 +287,18 @@ mod test {
         let instrument_spec = InstrumentSpec {
             multiplier: 20.0,
             fee: 1.5,
+            roll_fee: 0.0,
         };
         let mut position = Position::new(instrument_id.clone(), instrument_spec);
         let order1 = Order {
             instrument_id: instrument_id.clone(),
             size: 1,
+            signal: Signal::default(),
         };
         let order2 = Order {
             instrument_id: instrument_id.clone(),
             size: -2,
+            signal: Signal::default(),
         };
         position.on_order(order1.clone());
         position.on_bar(&bars[0]);
@@ -177,5 +310,17 @@ mod test {
         let profit = position.realized_profit();
         println!("profit: {}", profit);
         assert!((expected_profit - profit).abs() < 1e-6);
+
+        // long 1 at 100 and 1 at 101, closed at 102 two bars after the first entry
+        let trades = position.trades();
+        assert_eq!(trades.len(), 1);
+        assert_eq!((trades[0].side, trades[0].size), (TradeSide::Long, 2));
+        assert_eq!(
+            (trades[0].entry_price, trades[0].exit_price),
+            (100.5, 102.0)
+        );
+        assert_eq!((trades[0].fees, trades[0].bars_held), (3.0, 2));
+        assert_eq!((trades[0].mae, trades[0].mfe), (0.0, 60.0));
+        assert_eq!(trades[0].profit, profit);
     }
 }
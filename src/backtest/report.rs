@@ -0,0 +1,160 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backtest::{Bootstrap, BootstrapIntervals, Interval, Profit};
+use crate::config::BootstrapConfig;
+
+/// Summary statistics of realized trade profits, in USD.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestReport {
+    pub n_trades: usize,
+    pub total_profit: f64,
+    pub mean_profit: f64,
+    pub std_profit: f64,
+    /// Share of trades with a positive profit
+    pub win_rate: f64,
+    /// Gross profit over gross loss
+    #[serde(deserialize_with = "null_as_nan")]
+    pub profit_factor: f64,
+    /// Largest drop of the cumulative profit from its running maximum
+    pub max_drawdown: f64,
+    /// Mean over standard deviation of trade profits
+    #[serde(deserialize_with = "null_as_nan")]
+    pub sharpe_per_trade: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bootstrap: Option<Bootstrap>,
+}
+
+/// JSON has no NaN or infinity, which serde_json writes as null.
+pub(crate) fn null_as_nan<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<f64, D::Error> {
+    Ok(Option::<f64>::deserialize(deserializer)?.unwrap_or(f64::NAN))
+}
+
+impl BacktestReport {
+    pub fn new(profits: &[Profit]) -> Self {
+        let n: usize = profits.len();
+        let n_f64: f64 = n.max(1) as f64;
+        let total_profit: f64 = profits.iter().map(|p| p.profit).sum();
+        let mean_profit: f64 = total_profit / n_f64;
+        let variance: f64 = profits
+            .iter()
+            .map(|p| (p.profit - mean_profit).powi(2))
+            .sum::<f64>()
+            / n_f64;
+        let gross_profit: f64 = profits.iter().map(|p| p.profit.max(0.0)).sum();
+        let gross_loss: f64 = -profits.iter().map(|p| p.profit.min(0.0)).sum::<f64>();
+        let mut cumulative: f64 = 0.0;
+        let mut peak: f64 = 0.0;
+        let mut max_drawdown: f64 = 0.0;
+        for profit in profits {
+            cumulative += profit.profit;
+            peak = peak.max(cumulative);
+            max_drawdown = max_drawdown.max(peak - cumulative);
+        }
+        Self {
+            n_trades: n,
+            total_profit,
+            mean_profit,
+            std_profit: variance.sqrt(),
+            win_rate: profits.iter().filter(|p| p.profit > 0.0).count() as f64 / n_f64,
+            profit_factor: gross_profit / gross_loss,
+            max_drawdown,
+            sharpe_per_trade: mean_profit / variance.sqrt(),
+            bootstrap: None,
+        }
+    }
+
+    /// Adds the bootstrap confidence intervals of the same profits.
+    pub fn with_bootstrap(mut self, profits: &[Profit], config: &BootstrapConfig) -> Self {
+        self.bootstrap = Bootstrap::new(profits, config);
+        self
+    }
+}
+
+impl fmt::Display for BacktestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "N trades        : {}", self.n_trades)?;
+        writeln!(f, "Total profit    : {:.2} USD", self.total_profit)?;
+        writeln!(
+            f,
+            "Profit per trade: {:.2} ± {:.2} USD",
+            self.mean_profit, self.std_profit
+        )?;
+        writeln!(f, "Win rate        : {:.1}%", self.win_rate * 100.0)?;
+        writeln!(f, "Profit factor   : {:.3}", self.profit_factor)?;
+        writeln!(f, "Max drawdown    : {:.2} USD", self.max_drawdown)?;
+        write!(f, "Sharpe per trade: {:.3}", self.sharpe_per_trade)?;
+        if let Some(bootstrap) = &self.bootstrap {
+            write!(
+                f,
+                "\nBootstrap       : {} samples, {:.0}% intervals",
+                bootstrap.n_samples,
+                bootstrap.confidence * 100.0
+            )?;
+            write_intervals(f, "resampled trades, Sharpe per trade", &bootstrap.trades)?;
+            write_intervals(
+                f,
+                "resampled blocks of days, Sharpe per day",
+                &bootstrap.daily,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn write_intervals(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    intervals: &BootstrapIntervals,
+) -> fmt::Result {
+    let format = |interval: &Interval, precision: usize| {
+        format!(
+            "{:.*} [{:.*}, {:.*}]",
+            precision, interval.estimate, precision, interval.low, precision, interval.high
+        )
+    };
+    write!(f, "\n  {}:", name)?;
+    write!(
+        f,
+        "\n    Total profit  : {} USD",
+        format(&intervals.total_profit, 2)
+    )?;
+    write!(f, "\n    Sharpe        : {}", format(&intervals.sharpe, 3))?;
+    write!(
+        f,
+        "\n    Max drawdown  : {} USD",
+        format(&intervals.max_drawdown, 2)
+    )?;
+    write!(
+        f,
+        "\n    P(profit > 0) : {:.1}%",
+        intervals.prob_positive * 100.0
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let profits: Vec<Profit> = [10.0, -20.0, 5.0, 15.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &profit)| Profit {
+                timestamp: i as i64,
+                profit,
+            })
+            .collect();
+        let report = BacktestReport::new(&profits);
+        assert_eq!(report.n_trades, 4);
+        assert_eq!(report.total_profit, 10.0);
+        assert_eq!(report.win_rate, 0.75);
+        assert_eq!(report.profit_factor, 1.5);
+        assert_eq!(report.max_drawdown, 20.0);
+        assert!((report.std_profit - 181.25f64.sqrt()).abs() < 1e-9);
+    }
+}
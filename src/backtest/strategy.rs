@@ -3,5 +3,5 @@ use crate::dtypes::Bar;
 use crate::Point;
 
 pub trait Strategy<T: Point> {
-    fn trade_decision(&mut self, bar: &Bar<T>) -> Option<Order>;
+    fn trade_decision(&mut self, bar: &Bar<T>) -> anyhow::Result<Option<Order>>;
 }
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+/// Round trip of a position from flat, or from a reversal, until it is closed.
+/// Prices are mid prices and amounts are in USD.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    pub instrument: String,
+    pub side: TradeSide,
+    /// Largest number of contracts held
+    pub size: i32,
+    pub entry_timestamp: i64,
+    /// Average price of the entries
+    pub entry_price: f64,
+    pub exit_timestamp: i64,
+    /// Average price of the exits
+    pub exit_price: f64,
+    pub fees: f64,
+    /// Profit after fees
+    pub profit: f64,
+    pub bars_held: usize,
+    /// Lowest unrealized profit before fees while open, at most 0
+    pub mae: f64,
+    /// Highest unrealized profit before fees while open, at least 0
+    pub mfe: f64,
+    pub exit_reason: ExitReason,
+    pub entry_open_long_utility: Option<f64>,
+    pub entry_open_short_utility: Option<f64>,
+    pub exit_hold_utility: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeSide {
+    Long,
+    Short,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitReason {
+    /// Closed or reversed by an order
+    Order,
+    /// Closed at the last bar of a futures contract
+    Roll,
+}
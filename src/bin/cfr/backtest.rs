@@ -0,0 +1,225 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use toml::Value;
+
+use cfr_proto::strategies::{new_baseline, BasicStrategy};
+use cfr_proto::{
+    datasets, BacktestReport, Backtester, Config, DatasetFingerprint, DatasetRef, FeaturePoint,
+    InstrumentId, InstrumentSpec, ModelSet, ModelSetRef, ModelType, Position, Profit, RunDir,
+    Strategy, Trade,
+};
+
+type Point = FeaturePoint;
+use datasets::load_with_features_at_raw_prices as load_dataset;
+
+/// Overrides of config values, applied after `--set`
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Dataset path
+    #[clap(long)]
+    dataset: Option<String>,
+    /// Iteration whose models are backtested
+    #[clap(long)]
+    iteration: Option<usize>,
+    /// Run whose models are backtested, by id or `latest`
+    #[clap(long)]
+    run: Option<String>,
+    /// Models directory outside of the runs, used instead of the run
+    #[clap(long)]
+    models_dir: Option<String>,
+    /// CSV file the realized profits are written to
+    #[clap(long)]
+    profits_output_file: Option<String>,
+    /// CSV file the round trip trades are written to
+    #[clap(long)]
+    trades_output_file: Option<String>,
+}
+
+impl Args {
+    pub fn overrides(&self) -> Vec<(String, Value)> {
+        let mut overrides = Vec::new();
+        if let Some(value) = &self.dataset {
+            overrides.push(("dataset_path".to_string(), Value::String(value.clone())));
+        }
+        if let Some(value) = self.iteration {
+            overrides.push((
+                "backtest.iteration".to_string(),
+                Value::Integer(value as i64),
+            ));
+        }
+        if let Some(value) = &self.run {
+            overrides.push(("backtest.run".to_string(), Value::String(value.clone())));
+        }
+        if let Some(value) = &self.models_dir {
+            overrides.push((
+                "backtest.models_dir".to_string(),
+                Value::String(value.clone()),
+            ));
+        }
+        if let Some(value) = &self.profits_output_file {
+            overrides.push((
+                "backtest.profits_output_file".to_string(),
+                Value::String(value.clone()),
+            ));
+        }
+        if let Some(value) = &self.trades_output_file {
+            overrides.push((
+                "backtest.trades_output_file".to_string(),
+                Value::String(value.clone()),
+            ));
+        }
+        overrides
+    }
+}
+
+pub fn run(config: &Config) -> anyhow::Result<()> {
+    let instrument_id: InstrumentId = InstrumentId {
+        symbol: "NQ".to_string(),
+        index: 0,
+    };
+    let instrument_spec: InstrumentSpec = InstrumentSpec {
+        multiplier: 20.0,
+        fee: 1.65,
+        roll_fee: config
+            .roll
+            .as_ref()
+            .map_or(0.0, |roll| roll.roll_fee_per_contract_usd),
+    };
+    // loaded before the models, which are checked against its features
+    let offset: f64 = config.backtest.offset;
+    let limit: f64 = config.backtest.limit;
+    let dateset_ref: DatasetRef<Point> = load_dataset(
+        &config.dataset_path,
+        offset,
+        limit,
+        &config.features,
+        config.resample.as_ref(),
+        config.roll.as_ref(),
+    )?;
+    println!("Dataset size: {}", dateset_ref.len());
+    let new_positions = || -> Rc<RefCell<Vec<Position>>> {
+        let position: Position = Position::new(instrument_id.clone(), instrument_spec.clone());
+        Rc::new(RefCell::new(vec![position]))
+    };
+    let positions: Rc<RefCell<Vec<Position>>> = new_positions();
+    let model_set: ModelSetRef<Point> = match &config.backtest.ensemble {
+        Some(ensemble_config) => ModelSet::new_ensemble(
+            config.backtest.iteration,
+            &RunDir::ensemble_models_dirs(config, ensemble_config)?,
+            &config.model,
+            ensemble_config,
+            &dateset_ref.feature_names,
+            // the runs of an ensemble may train on different slices
+            None,
+        )?,
+        None => {
+            // the training slice, as model files trained on other data are warned about
+            let training_dataset: DatasetRef<Point> = datasets::load_with_features(
+                &config.dataset_path,
+                config.iteration.offset,
+                config.iteration.limit,
+                &config.features,
+                config.resample.as_ref(),
+                config.roll.as_ref(),
+            )?;
+            let training_hash: String =
+                DatasetFingerprint::new(&config.dataset_path, &training_dataset).hash;
+            let models_dir: String = RunDir::backtest_models_dir(config)?;
+            println!("Models: {}", models_dir);
+            let model_set = ModelSet::new(
+                config.backtest.iteration,
+                &models_dir,
+                &config.model,
+                &dateset_ref.feature_names,
+                Some(&training_hash),
+            )?;
+            model_set.load_model_params_with_close_from_previous_iteration()?;
+            model_set
+        }
+    };
+    for model_type in ModelType::all() {
+        let model = model_set.model(model_type);
+        println!("{}: {}", model_type, model.params()?);
+    }
+    let strategy: BasicStrategy<Point> =
+        BasicStrategy::new(positions.clone(), model_set, &config.backtest.strategy);
+    let strategy: Box<dyn Strategy<Point>> = Box::new(strategy);
+
+    let roll_timestamps: Vec<i64> = match &config.roll {
+        Some(roll_config) => {
+            datasets::roll_timestamps(&datasets::contracts(&config.dataset_path, roll_config)?)
+        }
+        None => vec![],
+    };
+    let mut backtester: Backtester<Point> =
+        Backtester::new(positions, dateset_ref.clone(), strategy)
+            .with_rolls(roll_timestamps.clone());
+    backtester.run()?;
+    let profits: &[Profit] = backtester.realized_profits();
+    let final_profit_usd: f64 = profits.iter().map(|profit| profit.profit).sum();
+    let final_profit_bps: f64 = final_profit_usd / backtester.traded_volume_usd() * 10000.0;
+    let report = BacktestReport::new(profits).with_bootstrap(profits, &config.backtest.bootstrap);
+    println!("{}", report);
+    println!(
+        "Final profit    : {:.3} bps of traded volume",
+        final_profit_bps
+    );
+    let mut csv_writer = csv::Writer::from_path(&config.backtest.profits_output_file)?;
+    for profit in profits {
+        csv_writer.serialize(profit)?;
+    }
+    let mut csv_writer = csv::Writer::from_path(&config.backtest.trades_output_file)?;
+    for trade in backtester.trades() {
+        csv_writer.serialize(trade)?;
+    }
+    println!("Trades: {}", config.backtest.trades_output_file);
+    // let profits_str = json!(&profits).to_string();
+    // std::fs::write(config.backtest.profits_output_file, profits_str).unwrap();
+    if let Some(path) = RunDir::write_backtest_report(config, &report)? {
+        println!("Report: {}", path.display());
+    }
+
+    if !config.backtest.baselines.is_empty() {
+        let trades: Vec<Trade> = backtester.trades();
+        let mut reports: Vec<(String, BacktestReport)> = vec![("models".to_string(), report)];
+        for baseline in config.backtest.baselines.iter() {
+            let positions: Rc<RefCell<Vec<Position>>> = new_positions();
+            let strategy = new_baseline(baseline, positions.clone(), &dateset_ref, &trades)?;
+            let mut backtester: Backtester<Point> =
+                Backtester::new(positions, dateset_ref.clone(), strategy)
+                    .with_rolls(roll_timestamps.clone());
+            backtester.run()?;
+            reports.push((
+                baseline.name(),
+                BacktestReport::new(backtester.realized_profits()),
+            ));
+        }
+        print_comparison(&reports);
+    }
+    Ok(())
+}
+
+/// One line per strategy backtested on the same data.
+fn print_comparison(reports: &[(String, BacktestReport)]) {
+    let width: usize = reports
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    println!(
+        "{:<width$}  {:>8}  {:>12}  {:>8}  {:>12}  {:>8}",
+        "Strategy", "Trades", "Profit USD", "Sharpe", "Drawdown USD", "Win rate"
+    );
+    for (name, report) in reports {
+        println!(
+            "{:<width$}  {:>8}  {:>12.2}  {:>8.3}  {:>12.2}  {:>7.1}%",
+            name,
+            report.n_trades,
+            report.total_profit,
+            report.sharpe_per_trade,
+            report.max_drawdown,
+            report.win_rate * 100.0
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use clap::Subcommand;
+
+use cfr_proto::datasets::binary::{self, Header};
+use cfr_proto::datasets::{self, CsvColumns, RawDataset};
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Convert a CSV file of bars into the binary dataset format
+    Convert {
+        /// Input CSV path
+        input: String,
+        /// Output binary path
+        output: String,
+        /// Column of timestamps, integer nanoseconds or ISO 8601 UTC
+        #[clap(long, default_value = "timestamp")]
+        timestamp_column: String,
+        /// Column of mid prices
+        #[clap(long, default_value = "mid_price")]
+        mid_price_column: String,
+        /// Column of futures contract codes, from which roll dates are recorded
+        #[clap(long)]
+        contract_column: Option<String>,
+        /// Comma-separated feature columns, all other columns by default
+        #[clap(long, value_delimiter = ',')]
+        features: Vec<String>,
+        /// Instrument symbol recorded in the header
+        #[clap(long)]
+        symbol: Option<String>,
+    },
+    /// Print the header of a binary dataset
+    Info {
+        /// Binary dataset path
+        path: String,
+    },
+}
+
+pub fn run(command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::Convert {
+            input,
+            output,
+            timestamp_column,
+            mid_price_column,
+            contract_column,
+            features,
+            symbol,
+        } => {
+            let columns = CsvColumns {
+                timestamp: timestamp_column,
+                mid_price: mid_price_column,
+                contract: contract_column,
+                features,
+            };
+            let mut dataset: RawDataset = datasets::read_csv(&input, &columns)?;
+            dataset.symbol = symbol;
+            dataset.sort_by_timestamp();
+            dataset.validate()?;
+            binary::write(&output, &dataset)?;
+            println!(
+                "Wrote {} rows with features {:?} to {}",
+                dataset.len(),
+                dataset.feature_names,
+                output
+            );
+            for contract in dataset.contracts.iter() {
+                println!(
+                    "Contract {} from {}",
+                    contract.code, contract.start_timestamp
+                );
+            }
+        }
+        Command::Info { path } => {
+            let header: Header = binary::read_header(&path)?;
+            println!("{}", serde_json::to_string_pretty(&header)?);
+        }
+    }
+    Ok(())
+}
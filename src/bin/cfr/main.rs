@@ -0,0 +1,88 @@
+mod backtest;
+mod dataset;
+mod models;
+mod report;
+mod sweep;
+mod train;
+
+use clap::{Parser, Subcommand};
+
+use cfr_proto::Config;
+
+#[derive(Debug, Parser)]
+#[clap(name = "cfr")]
+#[clap(about = "Prototype of counterfactual regret minimization for trading")]
+struct Cli {
+    /// Config path
+    #[clap(
+        short = 'c',
+        long = "config",
+        default_value = "io/config.toml",
+        global = true
+    )]
+    config_path: String,
+
+    /// Print the resolved config values with where each was set, and exit
+    #[clap(short = 'p', long = "print-config", global = true)]
+    print_config: bool,
+
+    /// Override a config value, e.g. `--set iteration.n_plays=1000`;
+    /// applied after the file and `CFR_*` environment variables
+    #[clap(long = "set", value_name = "KEY=VALUE", global = true)]
+    set: Vec<String>,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Train models over a range of iterations
+    Train(train::Args),
+    /// Backtest the models of an iteration
+    Backtest(backtest::Args),
+    /// Dataset tools
+    Dataset {
+        #[clap(subcommand)]
+        command: dataset::Command,
+    },
+    /// List saved models with their training details
+    Models(models::Args),
+    /// Summarize the profits written by a backtest
+    Report(report::Args),
+    /// Train and backtest over a grid or random search of config values
+    Sweep(sweep::Args),
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    if let Command::Dataset { command } = cli.command {
+        return dataset::run(command);
+    }
+    let mut overrides: Vec<(String, toml::Value)> = cli
+        .set
+        .iter()
+        .map(|text| Config::parse_override(text))
+        .collect::<anyhow::Result<_>>()?;
+    overrides.extend(match &cli.command {
+        Command::Train(args) => args.overrides(),
+        Command::Backtest(args) => args.overrides(),
+        Command::Models(args) => args.overrides(),
+        Command::Report(args) => args.overrides(),
+        Command::Sweep(_) => vec![],
+        Command::Dataset { .. } => unreachable!(),
+    });
+    let config: Config = Config::load(&cli.config_path, &overrides)?;
+    if cli.print_config {
+        print!("{}", config.to_string_with_sources()?);
+        return Ok(());
+    }
+    match cli.command {
+        Command::Train(_) => train::run(&config),
+        Command::Backtest(_) => backtest::run(&config),
+        Command::Models(args) => models::run(&config, &args),
+        Command::Report(_) => report::run(&config),
+        Command::Sweep(args) => sweep::run(&config, &cli.config_path, &cli.set, &args),
+        Command::Dataset { .. } => unreachable!(),
+    }
+}
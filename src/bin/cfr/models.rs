@@ -0,0 +1,85 @@
+use toml::Value;
+
+use cfr_proto::{Config, ModelFile, RunDir};
+
+/// Overrides of config values, applied after `--set`
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Run whose models are listed, by id or `latest`
+    #[clap(long)]
+    run: Option<String>,
+    /// Models directory outside of the runs, used instead of the run
+    #[clap(long)]
+    models_dir: Option<String>,
+    /// List only the models of this iteration
+    #[clap(long)]
+    iteration: Option<usize>,
+}
+
+impl Args {
+    pub fn overrides(&self) -> Vec<(String, Value)> {
+        let mut overrides = Vec::new();
+        if let Some(value) = &self.run {
+            overrides.push(("backtest.run".to_string(), Value::String(value.clone())));
+        }
+        if let Some(value) = &self.models_dir {
+            overrides.push((
+                "backtest.models_dir".to_string(),
+                Value::String(value.clone()),
+            ));
+        }
+        overrides
+    }
+}
+
+pub fn run(config: &Config, args: &Args) -> anyhow::Result<()> {
+    let models_dir: String = RunDir::backtest_models_dir(config)?;
+    let dir: &str = &models_dir;
+    let mut paths: Vec<std::path::PathBuf> = std::fs::read_dir(dir)
+        .map_err(|e| anyhow::anyhow!("failed to read models directory {}: {}", dir, e))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    paths.retain(|path| path.extension().is_some_and(|e| e == "json"));
+    paths.sort();
+    for path in paths {
+        let text: String = std::fs::read_to_string(&path)?;
+        let value: serde_json::Value = serde_json::from_str(&text)?;
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if value.get("format_version").is_none() {
+            if args.iteration.is_none() {
+                println!("{}: legacy model file without training details", name);
+            }
+            continue;
+        }
+        let file: ModelFile = match serde_json::from_value(value) {
+            Ok(file) => file,
+            Err(e) => {
+                println!("{}: malformed model file: {}", name, e);
+                continue;
+            }
+        };
+        if args
+            .iteration
+            .is_some_and(|iteration| iteration != file.iteration)
+        {
+            continue;
+        }
+        println!(
+            "{}: iteration {} {} {:?}, {} samples in {:.1}s, features {:?}",
+            name,
+            file.iteration,
+            file.model_type,
+            file.kind,
+            file.n_samples,
+            file.training_secs,
+            file.feature_names
+        );
+        if let Some(diagnostics) = &file.diagnostics {
+            println!("    in-sample: {}", diagnostics.in_sample);
+            if let Some(holdout) = &diagnostics.holdout {
+                println!("    holdout  : {}", holdout);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use toml::Value;
+
+use cfr_proto::{BacktestReport, Config, Profit};
+
+/// Overrides of config values, applied after `--set`
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// CSV file of realized profits written by a backtest
+    #[clap(long)]
+    profits_file: Option<String>,
+}
+
+impl Args {
+    pub fn overrides(&self) -> Vec<(String, Value)> {
+        let mut overrides = Vec::new();
+        if let Some(value) = &self.profits_file {
+            overrides.push((
+                "backtest.profits_output_file".to_string(),
+                Value::String(value.clone()),
+            ));
+        }
+        overrides
+    }
+}
+
+pub fn run(config: &Config) -> anyhow::Result<()> {
+    let path: &str = &config.backtest.profits_output_file;
+    let mut reader = csv::Reader::from_path(path)
+        .map_err(|e| anyhow::anyhow!("failed to open profits file {}: {}", path, e))?;
+    let profits: Vec<Profit> = reader.deserialize().collect::<Result<_, _>>()?;
+    println!(
+        "{}",
+        BacktestReport::new(&profits).with_bootstrap(&profits, &config.backtest.bootstrap)
+    );
+    Ok(())
+}
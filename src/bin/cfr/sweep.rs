@@ -0,0 +1,234 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use anyhow::anyhow;
+use toml::Value;
+
+use cfr_proto::sweep::{self, SweepPoint, SweepSpec};
+use cfr_proto::{BacktestReport, Config, RunDir};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Sweep spec path
+    spec: String,
+    /// Cores shared by the points run in parallel, overriding the spec
+    #[clap(long)]
+    cpus: Option<usize>,
+}
+
+/// Trains and backtests every point of the spec as `cfr train` and `cfr backtest`
+/// subprocesses with the point's values as `--set` overrides, each in its own runs
+/// directory, and writes the results ranked by the backtest report.
+pub fn run(config: &Config, config_path: &str, set: &[String], args: &Args) -> anyhow::Result<()> {
+    if config.run.is_some() || config.backtest.models_dir.is_some() {
+        return Err(anyhow!(
+            "a sweep trains and backtests new runs, unset run and backtest.models_dir to sweep"
+        ));
+    }
+    let spec: SweepSpec = SweepSpec::read(&args.spec)?;
+    let points: Vec<SweepPoint> = spec.points();
+    // resolved as the subprocesses will, so that no point fails or overlaps only later
+    let mut max_concurrency: usize = 1;
+    for (i, point) in points.iter().enumerate() {
+        let point_config: Config = point_config(config_path, set, point)
+            .map_err(|e| e.context(format!("point {} {}", i, format_point(point))))?;
+        max_concurrency = max_concurrency.max(point_config.iteration.concurrency as usize);
+    }
+    let cpus: usize = args
+        .cpus
+        .or(spec.cpus)
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+    let n_parallel: usize = (cpus / max_concurrency).max(1).min(points.len());
+    let sweep_dir: PathBuf = sweep::create_dir(&config.runs_dir)?;
+    std::fs::copy(&args.spec, sweep_dir.join("sweep.toml"))?;
+    println!(
+        "Sweep: {} points, {} in parallel, in {}",
+        points.len(),
+        n_parallel,
+        sweep_dir.display()
+    );
+
+    let command = PointCommand {
+        exe: std::env::current_exe()?,
+        config_path: std::fs::canonicalize(config_path)?,
+        set: set.to_vec(),
+        backtest_iteration: config.backtest.iteration,
+    };
+    let next: AtomicUsize = AtomicUsize::new(0);
+    let results: Mutex<Vec<(usize, anyhow::Result<BacktestReport>)>> = Mutex::new(Vec::new());
+    std::thread::scope(|scope| {
+        for _ in 0..n_parallel {
+            scope.spawn(|| loop {
+                let i: usize = next.fetch_add(1, Ordering::SeqCst);
+                let Some(point) = points.get(i) else {
+                    break;
+                };
+                let result = command.run(&sweep_dir.join(i.to_string()), point);
+                match &result {
+                    Ok(report) => println!("Point {} {}: {:.3}", i, format_point(point), {
+                        sweep::report_metric(report, &spec.rank_by).unwrap_or(f64::NAN)
+                    }),
+                    Err(e) => println!("Point {} {}: failed: {}", i, format_point(point), e),
+                }
+                results.lock().unwrap().push((i, result));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(i, _)| *i);
+    let mut ranked: Vec<(SweepPoint, BacktestReport)> = Vec::new();
+    let mut failed: Vec<(usize, String)> = Vec::new();
+    for (i, result) in results {
+        match result {
+            Ok(report) => {
+                let mut point: SweepPoint = points[i].clone();
+                point.insert(0, ("point".to_string(), Value::Integer(i as i64)));
+                ranked.push((point, report));
+            }
+            Err(e) => failed.push((i, e.to_string())),
+        }
+    }
+    spec.rank(&mut ranked);
+
+    let results_path: PathBuf = sweep_dir.join("results.csv");
+    write_results(&results_path, &points, &ranked, &failed)?;
+    println!("Ranked by {}:", spec.rank_by);
+    for (rank, (point, report)) in ranked.iter().enumerate() {
+        println!(
+            "{:>3}. {}: {:.3}",
+            rank + 1,
+            format_point(point),
+            sweep::report_metric(report, &spec.rank_by).unwrap_or(f64::NAN)
+        );
+    }
+    if !failed.is_empty() {
+        println!("{} of {} points failed", failed.len(), points.len());
+    }
+    println!("Results: {}", results_path.display());
+    Ok(())
+}
+
+struct PointCommand {
+    exe: PathBuf,
+    config_path: PathBuf,
+    set: Vec<String>,
+    backtest_iteration: usize,
+}
+
+impl PointCommand {
+    /// Trains and backtests one point in `dir`, logging each subcommand next to its run.
+    fn run(&self, dir: &Path, point: &SweepPoint) -> anyhow::Result<BacktestReport> {
+        std::fs::create_dir_all(dir)?;
+        let runs_dir: String = dir.to_string_lossy().to_string();
+        let mut set: Vec<String> = self.set.clone();
+        set.push(format!("runs_dir={}", Value::String(runs_dir.clone())));
+        set.push("backtest.run=latest".to_string());
+        let profits_path = dir.join("profits.csv").to_string_lossy().to_string();
+        set.push(format!(
+            "backtest.profits_output_file={}",
+            Value::String(profits_path)
+        ));
+        let trades_path = dir.join("trades.csv").to_string_lossy().to_string();
+        set.push(format!(
+            "backtest.trades_output_file={}",
+            Value::String(trades_path)
+        ));
+        set.extend(
+            point
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value)),
+        );
+        for subcommand in ["train", "backtest"] {
+            let log_path: PathBuf = dir.join(format!("{}.log", subcommand));
+            let log = std::fs::File::create(&log_path)?;
+            let mut command = Command::new(&self.exe);
+            command.arg("-c").arg(&self.config_path);
+            for text in set.iter() {
+                command.arg("--set").arg(text);
+            }
+            let status = command
+                .arg(subcommand)
+                .stdout(log.try_clone()?)
+                .stderr(log)
+                .status()?;
+            if !status.success() {
+                return Err(anyhow!(
+                    "{} exited with {}, see {}",
+                    subcommand,
+                    status,
+                    log_path.display()
+                ));
+            }
+        }
+        RunDir::read_backtest_report(&runs_dir, "latest", self.backtest_iteration)
+    }
+}
+
+/// Config of a point, checked to backtest out of sample.
+fn point_config(config_path: &str, set: &[String], point: &SweepPoint) -> anyhow::Result<Config> {
+    let mut overrides: Vec<(String, Value)> = set
+        .iter()
+        .map(|text| Config::parse_override(text))
+        .collect::<anyhow::Result<_>>()?;
+    overrides.extend(point.iter().cloned());
+    let config: Config = Config::load(config_path, &overrides)?;
+    sweep::check_out_of_sample(&config)?;
+    Ok(config)
+}
+
+fn format_point(point: &SweepPoint) -> String {
+    point
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// One row per point, best first and failed points last, with the point's values,
+/// every report metric and the error of failed points.
+fn write_results(
+    path: &Path,
+    points: &[SweepPoint],
+    ranked: &[(SweepPoint, BacktestReport)],
+    failed: &[(usize, String)],
+) -> anyhow::Result<()> {
+    let keys: Vec<&String> = points
+        .first()
+        .map(|point| point.iter().map(|(key, _)| key).collect())
+        .unwrap_or_default();
+    let metrics: Vec<String> = match serde_json::to_value(BacktestReport::new(&[]))? {
+        serde_json::Value::Object(fields) => fields.keys().cloned().collect(),
+        _ => vec![],
+    };
+    let mut writer = csv::Writer::from_path(path)?;
+    let mut header: Vec<String> = vec!["rank".to_string(), "point".to_string()];
+    header.extend(keys.iter().map(|key| key.to_string()));
+    header.extend(metrics.iter().cloned());
+    header.push("error".to_string());
+    writer.write_record(&header)?;
+    let format_value = |value: &Value| match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    };
+    for (rank, (point, report)) in ranked.iter().enumerate() {
+        let mut record: Vec<String> = vec![(rank + 1).to_string()];
+        record.extend(point.iter().map(|(_, value)| format_value(value)));
+        record.extend(metrics.iter().map(|name| {
+            sweep::report_metric(report, name).map_or(String::new(), |v| v.to_string())
+        }));
+        record.push(String::new());
+        writer.write_record(&record)?;
+    }
+    for (i, error) in failed {
+        let mut record: Vec<String> = vec![String::new(), i.to_string()];
+        record.extend(points[*i].iter().map(|(_, value)| format_value(value)));
+        record.extend(metrics.iter().map(|_| String::new()));
+        record.push(error.clone());
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
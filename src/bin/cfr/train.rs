@@ -0,0 +1,104 @@
+use toml::Value;
+
+use cfr_proto::{
+    datasets, Config, DatasetFingerprint, DatasetRef, FeaturePoint, Iteration, RunDir,
+};
+
+type Point = FeaturePoint;
+use datasets::load_with_features as load_dataset;
+
+/// Overrides of config values, applied after `--set`
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Dataset path
+    #[clap(long)]
+    dataset: Option<String>,
+    /// First iteration to train
+    #[clap(long)]
+    start_iteration: Option<u64>,
+    /// Number of iterations to train
+    #[clap(long)]
+    n_iterations: Option<u64>,
+    /// Plays per model and iteration
+    #[clap(long)]
+    n_plays: Option<u64>,
+    /// Run to continue, by id or `latest`, instead of creating a new one
+    #[clap(long)]
+    run: Option<String>,
+}
+
+impl Args {
+    pub fn overrides(&self) -> Vec<(String, Value)> {
+        let mut overrides = Vec::new();
+        if let Some(value) = &self.dataset {
+            overrides.push(("dataset_path".to_string(), Value::String(value.clone())));
+        }
+        if let Some(value) = self.start_iteration {
+            overrides.push(("start_iteration".to_string(), Value::Integer(value as i64)));
+        }
+        if let Some(value) = self.n_iterations {
+            overrides.push(("n_iterations".to_string(), Value::Integer(value as i64)));
+        }
+        if let Some(value) = self.n_plays {
+            overrides.push((
+                "iteration.n_plays".to_string(),
+                Value::Integer(value as i64),
+            ));
+        }
+        if let Some(value) = &self.run {
+            overrides.push(("run".to_string(), Value::String(value.clone())));
+        }
+        overrides
+    }
+}
+
+pub fn run(config: &Config) -> anyhow::Result<()> {
+    // println!("Reading dataset...");
+    let offset: f64 = config.iteration.offset;
+    let limit: f64 = config.iteration.limit;
+    let dateset_ref: DatasetRef<Point> = load_dataset(
+        &config.dataset_path,
+        offset,
+        limit,
+        &config.features,
+        config.resample.as_ref(),
+        config.roll.as_ref(),
+    )?;
+    println!("Dataset size: {}", dateset_ref.len());
+    let fingerprint = DatasetFingerprint::new(&config.dataset_path, &dateset_ref);
+    let mut run: RunDir = match &config.run {
+        Some(run_id) => {
+            let run = RunDir::open(&config.runs_dir, run_id, &fingerprint)?;
+            run.write_config(config, &format!("config_{}.toml", config.start_iteration))?;
+            run
+        }
+        None => {
+            if config.start_iteration > 1 {
+                return Err(anyhow::anyhow!(
+                    "start_iteration = {} needs the models of iteration {}, set run to continue an existing run",
+                    config.start_iteration,
+                    config.start_iteration - 1
+                ));
+            }
+            RunDir::create(&config.runs_dir, config, fingerprint)?
+        }
+    };
+    println!("Run: {} in {}", run.id(), run.path().display());
+    let mut iteration_config = config.iteration.clone();
+    iteration_config.output_dir = run.models_dir();
+    let start = config.start_iteration as usize;
+    let stop = start + config.n_iterations as usize;
+    for iteration_index in start..stop {
+        println!("Iteration: {}", iteration_index);
+        let iteration: Iteration<Point> = Iteration::new(
+            iteration_index,
+            dateset_ref.clone(),
+            iteration_config.clone(),
+            &config.model,
+        )?;
+        iteration.run()?;
+        println!("{}", iteration.summary()?);
+        run.record_iteration(iteration.metrics())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,518 @@
+mod overrides;
+mod validate;
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backtest::TradeSide;
+
+pub use overrides::Source;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Config {
+    pub dataset_path: String,
+    /// Directory of training runs, each in a subdirectory named by its id
+    #[serde(default = "Config::default_runs_dir")]
+    pub runs_dir: String,
+    /// Run to continue training, e.g. from `start_iteration` after an interruption;
+    /// a new run is created if unset
+    pub run: Option<String>,
+    /// Iteration 0 plays with random models, so training starts at 1 or later
+    #[serde(default = "Config::default_start_iteration")]
+    pub start_iteration: u64,
+    #[serde(default = "Config::default_n_iterations")]
+    pub n_iterations: u64,
+    #[serde(default)]
+    pub iteration: IterationConfig,
+    #[serde(default)]
+    pub backtest: BacktestConfig,
+    #[serde(default)]
+    pub model: ModelConfig,
+    /// Derived features appended to the dataset points at load time
+    #[serde(default)]
+    pub features: Vec<FeatureConfig>,
+    /// Coarser bars built from the dataset ones before features are derived
+    pub resample: Option<ResampleConfig>,
+    /// Futures roll handling, for datasets spanning several contracts
+    pub roll: Option<RollConfig>,
+    /// Source of each value set by the file or overrides, by dotted key
+    #[serde(skip)]
+    pub sources: BTreeMap<String, Source>,
+}
+
+impl Config {
+    fn default_runs_dir() -> String {
+        "io/runs".to_string()
+    }
+
+    fn default_start_iteration() -> u64 {
+        1
+    }
+
+    fn default_n_iterations() -> u64 {
+        1
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RollConfig {
+    #[serde(default)]
+    pub adjustment: PriceAdjustment,
+    /// Charged per contract when a backtest position is rolled, on top of the closing fee
+    #[serde(default)]
+    pub roll_fee_per_contract_usd: f64,
+    /// Contracts in order, overriding those recorded in the dataset header
+    #[serde(default)]
+    pub contracts: Vec<ContractConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ContractConfig {
+    pub code: String,
+    /// First timestamp of the contract, integer nanoseconds or ISO 8601 UTC
+    pub start: String,
+}
+
+/// How prices before a roll are adjusted to remove the gap to the next contract
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceAdjustment {
+    /// Keep the raw prices, gaps included
+    None,
+    /// Add the gap, keeping price differences
+    #[default]
+    Back,
+    /// Multiply by the ratio of prices, keeping returns
+    Ratio,
+}
+
+/// Either `every_bars` or `interval_secs` must be set.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ResampleConfig {
+    /// Aggregate every N consecutive bars
+    pub every_bars: Option<usize>,
+    /// Aggregate bars within UTC-aligned time intervals
+    pub interval_secs: Option<u64>,
+    #[serde(default)]
+    pub mid_price: PriceAggregation,
+    /// Feature used as weights by `PriceAggregation::Vwap`
+    pub volume_feature: Option<String>,
+    /// Aggregation of features not listed in `features`
+    #[serde(default)]
+    pub default_aggregation: Aggregation,
+    /// Aggregation per feature name
+    #[serde(default)]
+    pub features: std::collections::BTreeMap<String, Aggregation>,
+}
+
+/// How values of a feature are combined into a resampled bar, ignoring NaNs except for `Last`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregation {
+    #[default]
+    Last,
+    Mean,
+    Min,
+    Max,
+    Sum,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceAggregation {
+    #[default]
+    Last,
+    /// Time-weighted, i.e. the mean over bars
+    Mean,
+    /// Weighted by `volume_feature`
+    Vwap,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct IterationConfig {
+    pub n_plays: u64,
+    /// Number of threads playing and training, all available cores by default
+    pub concurrency: u64,
+    /// Directory models are saved to and loaded from, set to the run's by `cfr train`
+    #[serde(skip)]
+    pub output_dir: String,
+    pub fee_per_contract_usd: f64,
+    pub multiplier: f64,
+    pub utility_penalty_bps: f64,
+    pub max_play_duration_in_bars: u64,
+    /// Start and length of the used part of the dataset, as fractions of its rows
+    pub offset: f64,
+    pub limit: f64,
+    /// Share of each model's samples held out of training for fit diagnostics
+    pub holdout_fraction: f64,
+    /// If set, plays of closing models start only where this policy opens their side,
+    /// at the cost of two opening model inferences per drawn start
+    pub opening_policy: Option<OpeningPolicy>,
+    /// Seed of the plays and of the holdout split, each worker's derived from it;
+    /// from entropy if unset. Random models of iteration 0 and model fitting are not seeded.
+    pub seed: Option<u64>,
+}
+
+impl Default for IterationConfig {
+    fn default() -> Self {
+        Self {
+            n_plays: 100_000,
+            concurrency: std::thread::available_parallelism().map_or(1, |n| n.get() as u64),
+            output_dir: String::new(),
+            fee_per_contract_usd: 1.65,
+            multiplier: 20.0,
+            utility_penalty_bps: 0.0,
+            max_play_duration_in_bars: 900,
+            offset: 0.0,
+            limit: 1.0,
+            holdout_fraction: 0.0,
+            opening_policy: None,
+            seed: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BacktestConfig {
+    /// Run whose models are backtested, by id or `latest`
+    pub run: String,
+    pub iteration: usize,
+    /// Models directory outside of the runs, used instead of `run` if set
+    pub models_dir: Option<String>,
+    pub profits_output_file: String,
+    /// CSV file the round trip trades are written to, with the utilities behind them
+    pub trades_output_file: String,
+    /// Start and length of the used part of the dataset, as fractions of its rows
+    pub offset: f64,
+    pub limit: f64,
+    pub strategy: StrategyConfig,
+    /// If set, the backtest combines several saved model sets instead of a single iteration
+    pub ensemble: Option<EnsembleConfig>,
+    pub bootstrap: BootstrapConfig,
+    /// Rule-based strategies backtested on the same data, to compare the models against
+    pub baselines: Vec<BaselineConfig>,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            run: "latest".to_string(),
+            iteration: 1,
+            models_dir: None,
+            profits_output_file: "io/profits.csv".to_string(),
+            trades_output_file: "io/trades.csv".to_string(),
+            offset: 0.0,
+            limit: 1.0,
+            strategy: StrategyConfig::default(),
+            ensemble: None,
+            bootstrap: BootstrapConfig::default(),
+            baselines: vec![],
+        }
+    }
+}
+
+/// Rule-based strategy trading one contract, e.g. `{ kind = "buy_and_hold" }`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BaselineConfig {
+    /// Long from the first bar, closed at the last
+    BuyAndHold,
+    /// Long or short with equal odds; by default entered as often and held as long
+    /// as the models' trades
+    Random {
+        #[serde(default)]
+        seed: u64,
+        /// Probability of entering at a bar without a position
+        entry_probability: Option<f64>,
+        holding_bars: Option<usize>,
+    },
+    /// Opens `side` when `feature` is above `threshold`, or below it with `below`,
+    /// and closes after `holding_bars`
+    Threshold {
+        feature: String,
+        threshold: f64,
+        #[serde(default)]
+        below: bool,
+        side: TradeSide,
+        holding_bars: usize,
+    },
+}
+
+impl BaselineConfig {
+    pub fn name(&self) -> String {
+        match self {
+            BaselineConfig::BuyAndHold => "buy and hold".to_string(),
+            BaselineConfig::Random { .. } => "random".to_string(),
+            BaselineConfig::Threshold {
+                feature,
+                threshold,
+                below,
+                side,
+                holding_bars,
+            } => format!(
+                "{:?} if {} {} {} for {} bars",
+                side,
+                feature,
+                if *below { "<" } else { ">" },
+                threshold,
+                holding_bars
+            )
+            .to_lowercase(),
+        }
+    }
+}
+
+/// Confidence intervals of the backtest report, resampling trades and blocks of daily profits
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BootstrapConfig {
+    /// Number of resampled profit sequences; 0 disables the bootstrap
+    pub n_samples: usize,
+    /// Length of the blocks of consecutive trading days resampled together
+    pub block_days: usize,
+    /// Coverage of the intervals, e.g. 0.95 for the 2.5% and 97.5% quantiles
+    pub confidence: f64,
+    pub seed: u64,
+}
+
+impl Default for BootstrapConfig {
+    fn default() -> Self {
+        Self {
+            n_samples: 1000,
+            block_days: 5,
+            confidence: 0.95,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EnsembleConfig {
+    /// Number of consecutive iterations ending at `backtest.iteration`
+    #[serde(default = "EnsembleConfig::default_n_iterations")]
+    pub n_iterations: usize,
+    /// Runs with different seeds, by id; with `models_dirs` empty, the backtest's own models
+    #[serde(default)]
+    pub runs: Vec<String>,
+    /// Models directories outside of the runs
+    #[serde(default)]
+    pub models_dirs: Vec<String>,
+    #[serde(default)]
+    pub combine: Combine,
+}
+
+impl EnsembleConfig {
+    fn default_n_iterations() -> usize {
+        1
+    }
+}
+
+/// How predictions of ensemble members are combined
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Combine {
+    #[default]
+    Mean,
+    Median,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StrategyConfig {
+    /// If set, open only when this quantile of the opening utility is positive too,
+    /// e.g. 0.1 to skip trades whose downside is likely to exceed fees
+    pub open_quantile: Option<f64>,
+    /// Side opened when both opening utilities are positive
+    pub opening_policy: OpeningPolicy,
+    /// Seed of the sampling policies
+    pub seed: u64,
+}
+
+/// Choice between long and short when both opening utilities are positive;
+/// with a single positive utility, its side is opened
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OpeningPolicy {
+    /// The side with the higher utility, long on ties
+    #[default]
+    Argmax,
+    /// Each side with probability proportional to its utility
+    Proportional,
+    /// Each side with probability proportional to `exp(utility / temperature)`
+    Softmax { temperature: f64 },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ModelConfig {
+    #[serde(default)]
+    pub kind: ModelKind,
+    /// Dotted path of the Python estimator class, e.g. `sklearn.linear_model.LinearRegression`
+    #[serde(default = "ModelConfig::default_estimator")]
+    pub estimator: String,
+    /// Keyword arguments passed to the Python estimator constructor
+    #[serde(default)]
+    pub params: toml::Table,
+    #[serde(default)]
+    pub gbdt: GbdtConfig,
+    #[serde(default)]
+    pub mlp: MlpConfig,
+    /// Feature preprocessing fitted during training and stored with each model
+    #[serde(default)]
+    pub transforms: Vec<TransformConfig>,
+}
+
+impl ModelConfig {
+    fn default_estimator() -> String {
+        "sklearn.linear_model.LinearRegression".to_string()
+    }
+}
+
+impl Default for ModelConfig {
+    fn default() -> Self {
+        Self {
+            kind: ModelKind::default(),
+            estimator: Self::default_estimator(),
+            params: toml::Table::new(),
+            gbdt: GbdtConfig::default(),
+            mlp: MlpConfig::default(),
+            transforms: vec![],
+        }
+    }
+}
+
+/// Feature computed from the bars when the dataset is loaded.
+/// `source` is a feature name, including earlier derived ones, or `mid_price`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FeatureConfig {
+    /// Log return of the mid price over `bars`
+    Return {
+        bars: usize,
+    },
+    /// Deviation from the rolling mean in rolling standard deviations
+    ZScore {
+        source: String,
+        window: usize,
+    },
+    Ewma {
+        source: String,
+        span: usize,
+    },
+    /// Rolling standard deviation of 1-bar log returns
+    Volatility {
+        window: usize,
+    },
+    /// Sine and cosine of the UTC time of day
+    TimeOfDay,
+    Lag {
+        source: String,
+        bars: usize,
+    },
+}
+
+/// Step of the feature preprocessing pipeline
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TransformConfig {
+    /// Subtract mean and divide by standard deviation
+    ZScore,
+    /// Clamp all features to `[min, max]`
+    Clip { min: f64, max: f64 },
+    /// Clamp each feature to its training quantiles
+    Winsorize {
+        lower_quantile: f64,
+        upper_quantile: f64,
+    },
+    /// Signed `ln(1 + |x|)`
+    Log,
+    /// Append powers `2..=degree` of each feature
+    Polynomial { degree: u32 },
+    /// Append pairwise products of features
+    Interactions,
+}
+
+/// Implementation used for the models trained on each iteration
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelKind {
+    /// Python estimator run through the embedded interpreter (`WorkingModel`)
+    #[default]
+    Python,
+    /// Native gradient-boosted regression trees (`GbdtModel`)
+    Gbdt,
+    /// Native multilayer perceptron (`MlpModel`)
+    Mlp,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GbdtConfig {
+    pub n_trees: usize,
+    pub learning_rate: f64,
+    pub max_depth: usize,
+    pub max_leaves: usize,
+    pub min_samples_leaf: usize,
+    /// Number of histogram bins per feature, at most 255, NaN values get a bin of their own
+    pub n_bins: usize,
+    /// Fraction of samples held out for early stopping
+    pub holdout_fraction: f64,
+    /// Stop after this many trees without holdout improvement
+    pub early_stopping_rounds: usize,
+    /// Utility quantiles predicted by extra ensembles trained on pinball loss
+    pub quantiles: Vec<f64>,
+    /// Fit an extra ensemble on squared deviations from the mean,
+    /// so that any quantile can be derived assuming normality
+    pub fit_variance: bool,
+}
+
+impl Default for GbdtConfig {
+    fn default() -> Self {
+        Self {
+            n_trees: 200,
+            learning_rate: 0.1,
+            max_depth: 4,
+            max_leaves: 16,
+            min_samples_leaf: 100,
+            n_bins: 64,
+            holdout_fraction: 0.2,
+            early_stopping_rounds: 10,
+            quantiles: vec![],
+            fit_variance: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MlpConfig {
+    /// Sizes of the hidden layers; the output layer is a single linear unit
+    pub hidden_layers: Vec<usize>,
+    pub activation: Activation,
+    /// Adam step size
+    pub learning_rate: f64,
+    pub batch_size: usize,
+    pub n_epochs: usize,
+}
+
+impl Default for MlpConfig {
+    fn default() -> Self {
+        Self {
+            hidden_layers: vec![32, 16],
+            activation: Activation::default(),
+            learning_rate: 1e-3,
+            batch_size: 256,
+            n_epochs: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Activation {
+    #[default]
+    Relu,
+    Tanh,
+}
@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use anyhow::anyhow;
+use toml::{Table, Value};
+
+use crate::config::Config;
+use crate::utils;
+
+const ENV_PREFIX: &str = "CFR_";
+
+/// Where a config value was set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    File,
+    /// Environment variable of the given name
+    Env(String),
+    /// `--set` or a subcommand flag
+    Cli,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File => write!(f, "file"),
+            Source::Env(name) => write!(f, "env {}", name),
+            Source::Cli => write!(f, "cli"),
+        }
+    }
+}
+
+impl Config {
+    /// Reads the TOML file, applies `CFR_*` environment overrides and then the command line
+    /// `overrides`, and validates the result. Environment variables name dotted keys in
+    /// upper case with `__` between tables, e.g. `CFR_ITERATION__N_PLAYS=1000`; those
+    /// naming no config key are ignored with a warning, unknown `overrides` are errors.
+    pub fn load(path: &str, overrides: &[(String, Value)]) -> anyhow::Result<Self> {
+        let config_path: String = utils::canonicalize_path(path)?;
+        let config_text: String = std::fs::read_to_string(&config_path)?;
+        let mut table: Table = toml::from_str(&config_text)
+            .map_err(|e| anyhow!("invalid config file {}: {}", config_path, e))?;
+        let mut sources: BTreeMap<String, Source> = BTreeMap::new();
+        for (key, _) in flatten(&table) {
+            sources.insert(key, Source::File);
+        }
+        let mut env_overrides: Vec<(String, String, String)> = std::env::vars()
+            .filter_map(|(name, value)| {
+                let key: String = name
+                    .strip_prefix(ENV_PREFIX)?
+                    .to_lowercase()
+                    .replace("__", ".");
+                Some((key, name, value))
+            })
+            .collect();
+        env_overrides.sort();
+        let mut applied: Vec<(String, Source)> = Vec::new();
+        for (key, name, value) in env_overrides {
+            if let Err(e) = set(&mut table, &key, parse_value(&value)) {
+                eprintln!("warning: ignoring {}: {}", name, e);
+                continue;
+            }
+            applied.push((key, Source::Env(name)));
+        }
+        for (key, value) in overrides {
+            set(&mut table, key, value.clone())
+                .map_err(|e| e.context(format!("invalid override of {}", key)))?;
+            applied.push((key.clone(), Source::Cli));
+        }
+        let mut config: Config = Value::Table(table)
+            .try_into()
+            .map_err(|e| anyhow!("invalid config {}: {}", config_path, e))?;
+        let resolved: BTreeMap<String, Value> = flatten(&Table::try_from(&config)?);
+        for (key, source) in applied {
+            let is_known = resolved
+                .keys()
+                .any(|k| k == &key || k.starts_with(&format!("{}.", key)));
+            match (is_known, &source) {
+                (true, _) => {
+                    sources.insert(key, source);
+                }
+                // the prefix may be shared with variables of other tools
+                (false, Source::Env(name)) => {
+                    eprintln!("warning: ignoring {}, {} is no config key", name, key);
+                }
+                (false, _) => {
+                    return Err(anyhow!("unknown config key {} set by {}", key, source));
+                }
+            }
+        }
+        config.sources = sources;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parses a `key=value` override, where the value is TOML or else a bare string.
+    pub fn parse_override(text: &str) -> anyhow::Result<(String, Value)> {
+        let (key, value) = text
+            .split_once('=')
+            .ok_or_else(|| anyhow!("override {:?} is not of the form key=value", text))?;
+        Ok((key.trim().to_string(), parse_value(value.trim())))
+    }
+
+    /// Resolved values, one dotted key per line, with where each was set.
+    pub fn to_string_with_sources(&self) -> anyhow::Result<String> {
+        let mut text = String::new();
+        for (key, value) in flatten(&Table::try_from(self)?) {
+            text += &format!("{} = {} # {}\n", key, value, self.source(&key));
+        }
+        Ok(text)
+    }
+
+    /// Source of the value at the dotted key, or of the closest table containing it.
+    pub fn source(&self, key: &str) -> Source {
+        let mut prefix: &str = key;
+        loop {
+            if let Some(source) = self.sources.get(prefix) {
+                return source.clone();
+            }
+            match prefix.rsplit_once('.') {
+                Some((parent, _)) => prefix = parent,
+                None => return Source::Default,
+            }
+        }
+    }
+}
+
+fn parse_value(text: &str) -> Value {
+    match toml::from_str::<Table>(&format!("value = {}", text)) {
+        Ok(mut table) => table
+            .remove("value")
+            .unwrap_or(Value::String(text.to_string())),
+        Err(_) => Value::String(text.to_string()),
+    }
+}
+
+/// Sets the value at a dotted key, creating missing tables.
+fn set(table: &mut Table, key: &str, value: Value) -> anyhow::Result<()> {
+    let (parents, name) = match key.rsplit_once('.') {
+        Some((parents, name)) => (parents.split('.').collect::<Vec<&str>>(), name),
+        None => (vec![], key),
+    };
+    let mut current: &mut Table = table;
+    for parent in parents {
+        current = current
+            .entry(parent)
+            .or_insert_with(|| Value::Table(Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| anyhow!("{} in {} is not a table", parent, key))?;
+    }
+    current.insert(name.to_string(), value);
+    Ok(())
+}
+
+/// Values by dotted key, descending into tables but not into arrays.
+fn flatten(table: &Table) -> BTreeMap<String, Value> {
+    let mut values = BTreeMap::new();
+    for (name, value) in table {
+        match value {
+            Value::Table(inner) if !inner.is_empty() => {
+                for (key, value) in flatten(inner) {
+                    values.insert(format!("{}.{}", name, key), value);
+                }
+            }
+            _ => {
+                values.insert(name.clone(), value.clone());
+            }
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn test_load_with_overrides() {
+        let dir = temp_dir("config_test");
+        let path = dir.join("config.toml").to_str().unwrap().to_string();
+        std::fs::write(
+            &path,
+            "dataset_path = \"data.bin\"\n[iteration]\nn_plays = 10\noffset = 0.5\n",
+        )
+        .unwrap();
+        let overrides = vec![
+            Config::parse_override("iteration.limit=0.5").unwrap(),
+            Config::parse_override("backtest.models_dir = models").unwrap(),
+        ];
+        let config = Config::load(&path, &overrides).unwrap();
+        assert_eq!(config.iteration.n_plays, 10);
+        assert_eq!(config.iteration.limit, 0.5);
+        assert_eq!(config.backtest.models_dir.as_deref(), Some("models"));
+        assert_eq!(config.source("iteration.n_plays"), Source::File);
+        assert_eq!(config.source("iteration.limit"), Source::Cli);
+        assert_eq!(config.source("iteration.concurrency"), Source::Default);
+
+        let too_long = vec![Config::parse_override("iteration.limit=0.75").unwrap()];
+        let error = Config::load(&path, &too_long).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("iteration.offset + iteration.limit"));
+        let unknown = vec![Config::parse_override("iteration.n_play=1").unwrap()];
+        assert!(Config::load(&path, &unknown).is_err());
+        // stray variables of other tools sharing the prefix
+        std::env::set_var("CFR_TEST_STRAY", "1");
+        std::env::set_var("CFR_TEST_STRAY__LEVEL", "debug");
+        let config = Config::load(&path, &overrides);
+        std::env::remove_var("CFR_TEST_STRAY");
+        std::env::remove_var("CFR_TEST_STRAY__LEVEL");
+        assert_eq!(config.unwrap().iteration.n_plays, 10);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,276 @@
+use anyhow::anyhow;
+
+use crate::config::{
+    BaselineConfig, Config, FeatureConfig, ModelKind, OpeningPolicy, TransformConfig,
+};
+use crate::datasets::parse_timestamp;
+
+/// Collects one message per invalid field.
+#[derive(Default)]
+struct Errors(Vec<String>);
+
+impl Errors {
+    fn check(&mut self, is_valid: bool, message: impl FnOnce() -> String) {
+        if !is_valid {
+            self.0.push(message());
+        }
+    }
+
+    fn check_fraction(&mut self, key: &str, value: f64) {
+        self.check((0.0..=1.0).contains(&value), || {
+            format!("{} = {} must be within [0, 1]", key, value)
+        });
+    }
+
+    fn check_slice(&mut self, key: &str, offset: f64, limit: f64) {
+        self.check_fraction(&format!("{}.offset", key), offset);
+        self.check(limit > 0.0 && limit <= 1.0, || {
+            format!("{}.limit = {} must be within (0, 1]", key, limit)
+        });
+        self.check(offset + limit <= 1.0 + 1e-9, || {
+            format!(
+                "{0}.offset + {0}.limit = {1} exceeds the dataset, must be at most 1",
+                key,
+                offset + limit
+            )
+        });
+    }
+
+    fn check_positive(&mut self, key: &str, value: f64) {
+        self.check(value > 0.0, || {
+            format!("{} = {} must be positive", key, value)
+        });
+    }
+
+    fn check_at_least_one(&mut self, key: &str, value: u64) {
+        self.check(value >= 1, || {
+            format!("{} = {} must be at least 1", key, value)
+        });
+    }
+}
+
+impl Config {
+    /// Checks values that would otherwise panic, hang or silently do nothing later,
+    /// reporting every invalid field.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut errors = Errors::default();
+        errors.check(!self.dataset_path.is_empty(), || {
+            "dataset_path must not be empty".to_string()
+        });
+        errors.check(self.start_iteration >= 1, || {
+            "start_iteration = 0 is not trained, iteration 0 plays with random models".to_string()
+        });
+        errors.check_at_least_one("n_iterations", self.n_iterations);
+
+        let iteration = &self.iteration;
+        errors.check_at_least_one("iteration.n_plays", iteration.n_plays);
+        errors.check_at_least_one("iteration.concurrency", iteration.concurrency);
+        errors.check_at_least_one(
+            "iteration.max_play_duration_in_bars",
+            iteration.max_play_duration_in_bars,
+        );
+        errors.check_positive("iteration.multiplier", iteration.multiplier);
+        errors.check(iteration.fee_per_contract_usd >= 0.0, || {
+            "iteration.fee_per_contract_usd must not be negative".to_string()
+        });
+        errors.check_slice("iteration", iteration.offset, iteration.limit);
+        errors.check((0.0..1.0).contains(&iteration.holdout_fraction), || {
+            format!(
+                "iteration.holdout_fraction = {} must be within [0, 1)",
+                iteration.holdout_fraction
+            )
+        });
+        if let Some(OpeningPolicy::Softmax { temperature }) = iteration.opening_policy {
+            errors.check_positive("iteration.opening_policy.temperature", temperature);
+        }
+
+        let backtest = &self.backtest;
+        errors.check(backtest.iteration >= 1, || {
+            "backtest.iteration = 0 has no trained models".to_string()
+        });
+        errors.check_slice("backtest", backtest.offset, backtest.limit);
+        if let Some(q) = backtest.strategy.open_quantile {
+            errors.check(q > 0.0 && q < 1.0, || {
+                format!(
+                    "backtest.strategy.open_quantile = {} must be within (0, 1)",
+                    q
+                )
+            });
+            let is_gbdt: bool = self.model.kind == ModelKind::Gbdt;
+            let gbdt = &self.model.gbdt;
+            errors.check(is_gbdt, || {
+                format!(
+                    "backtest.strategy.open_quantile needs model.kind = \"gbdt\", the only model predicting utility quantiles, not {:?}",
+                    self.model.kind
+                )
+            });
+            errors.check(
+                !is_gbdt
+                    || gbdt.fit_variance
+                    || gbdt.quantiles.iter().any(|&p| (p - q).abs() < 1e-9),
+                || {
+                    format!(
+                        "backtest.strategy.open_quantile = {} needs model.gbdt.fit_variance or {} in model.gbdt.quantiles",
+                        q, q
+                    )
+                },
+            );
+        }
+        if let OpeningPolicy::Softmax { temperature } = backtest.strategy.opening_policy {
+            errors.check_positive("backtest.strategy.opening_policy.temperature", temperature);
+        }
+        if let Some(ensemble) = &backtest.ensemble {
+            errors.check(
+                ensemble.n_iterations >= 1 && ensemble.n_iterations <= backtest.iteration,
+                || {
+                    format!(
+                        "backtest.ensemble.n_iterations = {} must be within [1, backtest.iteration = {}]",
+                        ensemble.n_iterations, backtest.iteration
+                    )
+                },
+            );
+        }
+        errors.check_at_least_one(
+            "backtest.bootstrap.block_days",
+            backtest.bootstrap.block_days as u64,
+        );
+        errors.check(
+            backtest.bootstrap.confidence > 0.0 && backtest.bootstrap.confidence < 1.0,
+            || {
+                format!(
+                    "backtest.bootstrap.confidence = {} must be within (0, 1)",
+                    backtest.bootstrap.confidence
+                )
+            },
+        );
+        for (i, baseline) in backtest.baselines.iter().enumerate() {
+            let key = format!("backtest.baselines[{}]", i);
+            match baseline {
+                BaselineConfig::BuyAndHold => {}
+                BaselineConfig::Random {
+                    entry_probability,
+                    holding_bars,
+                    ..
+                } => {
+                    if let Some(p) = entry_probability {
+                        errors.check(*p > 0.0 && *p <= 1.0, || {
+                            format!("{}: entry_probability = {} must be within (0, 1]", key, p)
+                        });
+                    }
+                    errors.check(*holding_bars != Some(0), || {
+                        format!("{}: holding_bars must be at least 1", key)
+                    });
+                }
+                BaselineConfig::Threshold { holding_bars, .. } => {
+                    errors.check(*holding_bars >= 1, || {
+                        format!("{}: holding_bars must be at least 1", key)
+                    });
+                }
+            }
+        }
+
+        let gbdt = &self.model.gbdt;
+        errors.check_at_least_one("model.gbdt.n_trees", gbdt.n_trees as u64);
+        errors.check_positive("model.gbdt.learning_rate", gbdt.learning_rate);
+        errors.check_at_least_one("model.gbdt.max_depth", gbdt.max_depth as u64);
+        errors.check_at_least_one("model.gbdt.max_leaves", gbdt.max_leaves as u64);
+        errors.check_at_least_one("model.gbdt.min_samples_leaf", gbdt.min_samples_leaf as u64);
+        errors.check((2..=255).contains(&gbdt.n_bins), || {
+            format!(
+                "model.gbdt.n_bins = {} must be within [2, 255]",
+                gbdt.n_bins
+            )
+        });
+        errors.check((0.0..1.0).contains(&gbdt.holdout_fraction), || {
+            format!(
+                "model.gbdt.holdout_fraction = {} must be within [0, 1)",
+                gbdt.holdout_fraction
+            )
+        });
+        for q in gbdt.quantiles.iter() {
+            errors.check(*q > 0.0 && *q < 1.0, || {
+                format!("model.gbdt.quantiles: {} must be within (0, 1)", q)
+            });
+        }
+        let mlp = &self.model.mlp;
+        errors.check(mlp.hidden_layers.iter().all(|&n| n >= 1), || {
+            format!(
+                "model.mlp.hidden_layers = {:?} must all be at least 1",
+                mlp.hidden_layers
+            )
+        });
+        errors.check_positive("model.mlp.learning_rate", mlp.learning_rate);
+        errors.check_at_least_one("model.mlp.batch_size", mlp.batch_size as u64);
+        errors.check_at_least_one("model.mlp.n_epochs", mlp.n_epochs as u64);
+        for (i, transform) in self.model.transforms.iter().enumerate() {
+            let key = format!("model.transforms[{}]", i);
+            match *transform {
+                TransformConfig::Clip { min, max } => errors.check(min < max, || {
+                    format!("{}: clip min = {} must be below max = {}", key, min, max)
+                }),
+                TransformConfig::Winsorize {
+                    lower_quantile,
+                    upper_quantile,
+                } => errors.check(
+                    0.0 <= lower_quantile
+                        && lower_quantile < upper_quantile
+                        && upper_quantile <= 1.0,
+                    || {
+                        format!(
+                            "{}: winsorize quantiles {} and {} must be increasing within [0, 1]",
+                            key, lower_quantile, upper_quantile
+                        )
+                    },
+                ),
+                TransformConfig::Polynomial { degree } => errors.check(degree >= 1, || {
+                    format!("{}: polynomial degree must be at least 1", key)
+                }),
+                _ => {}
+            }
+        }
+
+        for (i, feature) in self.features.iter().enumerate() {
+            let length: Option<usize> = match feature {
+                FeatureConfig::Return { bars } | FeatureConfig::Lag { bars, .. } => Some(*bars),
+                FeatureConfig::ZScore { window, .. } | FeatureConfig::Volatility { window } => {
+                    Some(*window)
+                }
+                FeatureConfig::Ewma { span, .. } => Some(*span),
+                FeatureConfig::TimeOfDay => None,
+            };
+            errors.check(length != Some(0), || {
+                format!("features[{}]: bars, window or span must be at least 1", i)
+            });
+        }
+
+        if let Some(resample) = &self.resample {
+            errors.check(
+                resample.every_bars.is_some() != resample.interval_secs.is_some(),
+                || "resample: exactly one of every_bars and interval_secs must be set".to_string(),
+            );
+            errors.check(
+                resample.every_bars != Some(0) && resample.interval_secs != Some(0),
+                || "resample: every_bars and interval_secs must be at least 1".to_string(),
+            );
+        }
+
+        if let Some(roll) = &self.roll {
+            errors.check(roll.roll_fee_per_contract_usd >= 0.0, || {
+                "roll.roll_fee_per_contract_usd must not be negative".to_string()
+            });
+            for contract in roll.contracts.iter() {
+                if let Err(e) = parse_timestamp(&contract.start) {
+                    errors
+                        .0
+                        .push(format!("roll.contracts: {}: {}", contract.code, e));
+                }
+            }
+        }
+
+        if errors.0.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("invalid config:\n  {}", errors.0.join("\n  ")))
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use std::io::{BufReader, BufWriter, Read, Write};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::datasets::{ContractSpan, RawDataset};
+use crate::utils::Fnv1a;
+
+const MAGIC: &[u8; 8] = b"CFRDATA\0";
+/// Version 2 added the futures `contracts` to the header. Version 1 headers read
+/// as having none, while older readers would drop them from version 2 files.
+const VERSION: u32 = 2;
+const MIN_VERSION: u32 = 1;
+/// Bound on the JSON header length, well above that of any column list and contracts
+const MAX_HEADER_LEN: u64 = 16 << 20;
+const TIMESTAMP: &str = "timestamp";
+const MID_PRICE: &str = "mid_price";
+
+/// Schema and summary of a dataset file, stored as JSON after the magic and version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Header {
+    pub n_rows: u64,
+    /// `timestamp` and `mid_price` followed by features, in row order
+    pub columns: Vec<Column>,
+    pub symbol: Option<String>,
+    /// First and last timestamps, nanoseconds since the epoch
+    pub start_timestamp: i64,
+    pub end_timestamp: i64,
+    /// FNV-1a hash of the row bytes, as a hex string
+    pub checksum: String,
+    /// Futures contracts by start timestamp, if the rows span several
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contracts: Vec<ContractSpan>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Column {
+    pub name: String,
+    pub dtype: DType,
+}
+
+/// Little-endian column types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DType {
+    I64,
+    F64,
+}
+
+impl Header {
+    fn feature_names(&self) -> Vec<String> {
+        self.columns[2..].iter().map(|c| c.name.clone()).collect()
+    }
+
+    fn row_size(&self) -> usize {
+        8 * self.columns.len()
+    }
+
+    fn check_columns(&self) -> anyhow::Result<()> {
+        let expected_prefix = [(TIMESTAMP, DType::I64), (MID_PRICE, DType::F64)];
+        let prefix_matches = self.columns.len() >= 2
+            && self
+                .columns
+                .iter()
+                .zip(expected_prefix)
+                .all(|(column, (name, dtype))| column.name == name && column.dtype == dtype);
+        if !prefix_matches || self.columns[2..].iter().any(|c| c.dtype != DType::F64) {
+            return Err(anyhow!(
+                "unsupported columns {:?}, expected i64 timestamp, f64 mid_price and f64 features",
+                self.columns
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Writes the magic, the version, the length-prefixed JSON `Header`
+/// and the rows of `timestamp: i64, mid_price: f64, features: [f64; n_features]`.
+pub fn write(path: &str, dataset: &RawDataset) -> anyhow::Result<()> {
+    dataset.validate()?;
+    let mut rows: Vec<u8> = Vec::with_capacity(dataset.len() * 8 * (2 + dataset.features.len()));
+    for row in 0..dataset.len() {
+        rows.extend_from_slice(&dataset.timestamps[row].to_le_bytes());
+        rows.extend_from_slice(&dataset.mid_prices[row].to_le_bytes());
+        for column in dataset.features.iter() {
+            rows.extend_from_slice(&column[row].to_le_bytes());
+        }
+    }
+    let mut columns = vec![
+        Column {
+            name: TIMESTAMP.to_string(),
+            dtype: DType::I64,
+        },
+        Column {
+            name: MID_PRICE.to_string(),
+            dtype: DType::F64,
+        },
+    ];
+    columns.extend(dataset.feature_names.iter().map(|name| Column {
+        name: name.clone(),
+        dtype: DType::F64,
+    }));
+    let header = Header {
+        n_rows: dataset.len() as u64,
+        columns,
+        symbol: dataset.symbol.clone(),
+        start_timestamp: dataset.timestamps[0],
+        end_timestamp: dataset.timestamps[dataset.len() - 1],
+        checksum: checksum(&rows),
+        contracts: dataset.contracts.clone(),
+    };
+    let header_json: Vec<u8> = serde_json::to_vec(&header)?;
+    let file = std::fs::File::create(path)
+        .map_err(|e| anyhow!("failed to create dataset file {}: {}", path, e))?;
+    let mut writer = BufWriter::new(file);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&(header_json.len() as u32).to_le_bytes())?;
+    writer.write_all(&header_json)?;
+    writer.write_all(&rows)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Whether the file starts with the magic written by `write`,
+/// as opposed to the headerless `nq_f1_f2_f4` layout.
+pub fn has_header(path: &str) -> anyhow::Result<bool> {
+    let mut magic = [0u8; 8];
+    let mut file = std::fs::File::open(path)
+        .map_err(|e| anyhow!("failed to open dataset file {}: {}", path, e))?;
+    Ok(file.read_exact(&mut magic).is_ok() && &magic == MAGIC)
+}
+
+pub fn read_header(path: &str) -> anyhow::Result<Header> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| anyhow!("failed to open dataset file {}: {}", path, e))?;
+    let file_len: u64 = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    read_header_from(&mut reader, file_len)
+        .map_err(|e| e.context(format!("invalid dataset file {}", path)))
+}
+
+/// Reads the dataset and verifies it against the header.
+pub fn read(path: &str) -> anyhow::Result<RawDataset> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| anyhow!("failed to open dataset file {}: {}", path, e))?;
+    let file_len: u64 = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    read_from(&mut reader, file_len)
+        .map_err(|e| e.context(format!("invalid dataset file {}", path)))
+}
+
+/// `file_len` bounds the header length, so a corrupt length fails before allocating.
+fn read_header_from(reader: &mut impl Read, file_len: u64) -> anyhow::Result<Header> {
+    let magic: [u8; 8] = read_array(reader)?;
+    if &magic != MAGIC {
+        return Err(anyhow!("no dataset header"));
+    }
+    let version: u32 = u32::from_le_bytes(read_array(reader)?);
+    if !(MIN_VERSION..=VERSION).contains(&version) {
+        return Err(anyhow!(
+            "unsupported version {}, expected {} to {}; convert the source data again",
+            version,
+            MIN_VERSION,
+            VERSION
+        ));
+    }
+    let header_len: u64 = u32::from_le_bytes(read_array(reader)?) as u64;
+    let available: u64 = file_len.saturating_sub((MAGIC.len() + 8) as u64);
+    if header_len > MAX_HEADER_LEN || header_len > available {
+        return Err(anyhow!(
+            "header length {} exceeds the {} bytes after it or the limit of {}",
+            header_len,
+            available,
+            MAX_HEADER_LEN
+        ));
+    }
+    let mut header_json = vec![0u8; header_len as usize];
+    reader.read_exact(&mut header_json)?;
+    let header: Header = serde_json::from_slice(&header_json)?;
+    header.check_columns()?;
+    Ok(header)
+}
+
+fn read_from(reader: &mut impl Read, file_len: u64) -> anyhow::Result<RawDataset> {
+    let header: Header = read_header_from(reader, file_len)?;
+    let mut rows: Vec<u8> = Vec::new();
+    reader.read_to_end(&mut rows)?;
+    let expected_len: Option<u64> = header.n_rows.checked_mul(header.row_size() as u64);
+    if Some(rows.len() as u64) != expected_len {
+        return Err(anyhow!(
+            "{} bytes of rows, header describes {} rows of {} bytes",
+            rows.len(),
+            header.n_rows,
+            header.row_size()
+        ));
+    }
+    let actual_checksum = checksum(&rows);
+    if actual_checksum != header.checksum {
+        return Err(anyhow!(
+            "checksum {} does not match header checksum {}",
+            actual_checksum,
+            header.checksum
+        ));
+    }
+    let n_features: usize = header.columns.len() - 2;
+    let mut dataset = RawDataset {
+        feature_names: header.feature_names(),
+        features: vec![Vec::with_capacity(header.n_rows as usize); n_features],
+        symbol: header.symbol.clone(),
+        contracts: header.contracts.clone(),
+        ..RawDataset::default()
+    };
+    for row in rows.chunks_exact(header.row_size()) {
+        let mut words = row.chunks_exact(8).map(|w| <[u8; 8]>::try_from(w).unwrap());
+        dataset
+            .timestamps
+            .push(i64::from_le_bytes(words.next().unwrap()));
+        dataset
+            .mid_prices
+            .push(f64::from_le_bytes(words.next().unwrap()));
+        for (column, word) in dataset.features.iter_mut().zip(words) {
+            column.push(f64::from_le_bytes(word));
+        }
+    }
+    let time_range = (dataset.timestamps.first(), dataset.timestamps.last());
+    if time_range != (Some(&header.start_timestamp), Some(&header.end_timestamp)) {
+        return Err(anyhow!(
+            "time range {:?} does not match header range {}..{}",
+            time_range,
+            header.start_timestamp,
+            header.end_timestamp
+        ));
+    }
+    Ok(dataset)
+}
+
+fn checksum(rows: &[u8]) -> String {
+    let mut hasher = Fnv1a::default();
+    hasher.update(rows);
+    format!("{:016x}", hasher.finish())
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> anyhow::Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn file_bytes() -> Vec<u8> {
+        let header = serde_json::to_vec(&serde_json::json!({
+            "n_rows": 1,
+            "columns": [
+                {"name": TIMESTAMP, "dtype": "i64"},
+                {"name": MID_PRICE, "dtype": "f64"},
+            ],
+            "symbol": null,
+            "start_timestamp": 7,
+            "end_timestamp": 7,
+            "checksum": checksum(&[7i64.to_le_bytes(), 1.5f64.to_le_bytes()].concat()),
+        }))
+        .unwrap();
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&(header.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&header);
+        bytes.extend_from_slice(&7i64.to_le_bytes());
+        bytes.extend_from_slice(&1.5f64.to_le_bytes());
+        bytes
+    }
+
+    fn read_bytes(bytes: &[u8]) -> anyhow::Result<RawDataset> {
+        read_from(&mut std::io::Cursor::new(bytes), bytes.len() as u64)
+    }
+
+    #[test]
+    fn test_read_checks_version_and_header_length() {
+        // version 1 files, without contracts, still read
+        let dataset = read_bytes(&file_bytes()).unwrap();
+        assert_eq!(dataset.timestamps, vec![7]);
+        assert!(dataset.contracts.is_empty());
+
+        let mut future_version = file_bytes();
+        future_version[8..12].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert!(read_bytes(&future_version).is_err());
+
+        for header_len in [u32::MAX, 1 << 20] {
+            let mut corrupt = file_bytes();
+            corrupt[12..16].copy_from_slice(&header_len.to_le_bytes());
+            let error = read_bytes(&corrupt).unwrap_err().to_string();
+            assert!(error.contains("header length"), "{}", error);
+        }
+        let truncated = file_bytes()[..20].to_vec();
+        assert!(read_bytes(&truncated).is_err());
+    }
+}
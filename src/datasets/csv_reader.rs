@@ -0,0 +1,199 @@
+use anyhow::anyhow;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+use crate::datasets::{contracts_from_rows, RawDataset};
+
+/// Which CSV columns hold timestamps, mid prices, futures contract codes and features.
+#[derive(Debug, Clone)]
+pub struct CsvColumns {
+    pub timestamp: String,
+    pub mid_price: String,
+    /// Contract code of each row, for datasets spanning futures rolls
+    pub contract: Option<String>,
+    /// All other columns if empty
+    pub features: Vec<String>,
+}
+
+/// Reads rows in file order. Timestamps are integer nanoseconds since the epoch or
+/// ISO 8601 UTC date-times; empty feature values are read as NaN.
+pub fn read_csv(path: &str, columns: &CsvColumns) -> anyhow::Result<RawDataset> {
+    let mut reader = csv::Reader::from_path(path)
+        .map_err(|e| anyhow!("failed to open csv file {}: {}", path, e))?;
+    let headers: Vec<String> = reader
+        .headers()?
+        .iter()
+        .map(|h| h.trim().to_string())
+        .collect();
+    let find = |name: &str| -> anyhow::Result<usize> {
+        headers
+            .iter()
+            .position(|h| h == name)
+            .ok_or_else(|| anyhow!("column {:?} not found in {:?}", name, headers))
+    };
+    let timestamp_index: usize = find(&columns.timestamp)?;
+    let mid_price_index: usize = find(&columns.mid_price)?;
+    let contract_index: Option<usize> = columns.contract.as_deref().map(find).transpose()?;
+    let feature_names: Vec<String> = if columns.features.is_empty() {
+        headers
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| {
+                *i != timestamp_index && *i != mid_price_index && Some(*i) != contract_index
+            })
+            .map(|(_, h)| h.clone())
+            .collect()
+    } else {
+        columns.features.clone()
+    };
+    let feature_indices: Vec<usize> = feature_names
+        .iter()
+        .map(|name| find(name))
+        .collect::<anyhow::Result<_>>()?;
+    let mut dataset = RawDataset {
+        features: vec![Vec::new(); feature_names.len()],
+        feature_names,
+        ..RawDataset::default()
+    };
+    let mut contract_codes: Vec<String> = Vec::new();
+    for (row, record) in reader.records().enumerate() {
+        let record = record?;
+        let field = |index: usize| record.get(index).unwrap_or("").trim();
+        let context = || format!("{}: row {}", path, row + 1);
+        dataset
+            .timestamps
+            .push(parse_timestamp(field(timestamp_index)).map_err(|e| e.context(context()))?);
+        dataset
+            .mid_prices
+            .push(parse_f64(field(mid_price_index)).map_err(|e| e.context(context()))?);
+        for (column, &index) in dataset.features.iter_mut().zip(feature_indices.iter()) {
+            column.push(parse_f64(field(index)).map_err(|e| e.context(context()))?);
+        }
+        if let Some(index) = contract_index {
+            if field(index).is_empty() {
+                return Err(anyhow!("empty contract code").context(context()));
+            }
+            contract_codes.push(field(index).to_string());
+        }
+    }
+    if contract_index.is_some() {
+        dataset.contracts = contracts_from_rows(&dataset.timestamps, &contract_codes)?;
+    }
+    Ok(dataset)
+}
+
+fn parse_f64(value: &str) -> anyhow::Result<f64> {
+    if value.is_empty() {
+        return Ok(f64::NAN);
+    }
+    value
+        .parse::<f64>()
+        .map_err(|_| anyhow!("invalid number {:?}", value))
+}
+
+/// Parses integer nanoseconds or `YYYY-MM-DD[T ]HH:MM:SS[.fraction][Z|+00:00]`.
+pub fn parse_timestamp(value: &str) -> anyhow::Result<i64> {
+    if let Ok(nanos) = value.parse::<i64>() {
+        return Ok(nanos);
+    }
+    let invalid = || anyhow!("invalid timestamp {:?}", value);
+    let value = value
+        .strip_suffix('Z')
+        .or_else(|| value.strip_suffix("+00:00"))
+        .unwrap_or(value);
+    if value.len() < 19 || !value.is_char_boundary(19) {
+        return Err(invalid());
+    }
+    let (date_time, fraction) = value.split_at(19);
+    let bytes = date_time.as_bytes();
+    if bytes[4] != b'-' || bytes[7] != b'-' || !matches!(bytes[10], b'T' | b' ') {
+        return Err(invalid());
+    }
+    let number = |range: std::ops::Range<usize>| -> anyhow::Result<i64> {
+        date_time[range].parse::<i64>().map_err(|_| invalid())
+    };
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    let nanos: i64 = match fraction.strip_prefix('.') {
+        Some(digits) if !digits.is_empty() && digits.len() <= 9 => {
+            let digits_value: i64 = digits.parse().map_err(|_| invalid())?;
+            digits_value * 10i64.pow(9 - digits.len() as u32)
+        }
+        None if fraction.is_empty() => 0,
+        _ => return Err(invalid()),
+    };
+    // rejects impossible dates and times, e.g. February 31 or second 60
+    let date =
+        NaiveDate::from_ymd_opt(year as i32, month as u32, day as u32).ok_or_else(invalid)?;
+    let time =
+        NaiveTime::from_hms_nano_opt(hour as u32, minute as u32, second as u32, nanos as u32)
+            .ok_or_else(invalid)?;
+    NaiveDateTime::new(date, time)
+        .and_utc()
+        .timestamp_nanos_opt()
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::datasets::binary;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn test_csv_to_binary() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z").unwrap(), 0);
+        assert_eq!(
+            parse_timestamp("2024-02-29 12:30:00.5").unwrap(),
+            1_709_209_800_500_000_000
+        );
+        assert!(parse_timestamp("2024-13-01T00:00:00Z").is_err());
+        assert!(parse_timestamp("2024-02-31T00:00:00Z").is_err());
+        assert!(parse_timestamp("2023-02-29T00:00:00Z").is_err());
+        assert!(parse_timestamp("2024-04-31T00:00:00Z").is_err());
+        assert!(parse_timestamp("2024-01-01T24:00:00Z").is_err());
+        assert!(parse_timestamp("2024-01-01T23:59:60Z").is_err());
+        assert!(parse_timestamp("2024-01-01T00:00:00.1234567890").is_err());
+        assert!(parse_timestamp("2300-01-01T00:00:00Z").is_err());
+
+        let dir = temp_dir("csv_test");
+        let csv_path = dir.join("bars.csv").to_str().unwrap().to_string();
+        let bin_path = dir.join("bars.bin").to_str().unwrap().to_string();
+        std::fs::write(
+            &csv_path,
+            "ts,mp,f1,f2\n2024-01-01T00:00:01Z,100.5,1,\n2024-01-01T00:00:00Z,100.0,0.5,nan\n",
+        )
+        .unwrap();
+        let columns = CsvColumns {
+            timestamp: "ts".to_string(),
+            mid_price: "mp".to_string(),
+            contract: None,
+            features: vec![],
+        };
+        let mut dataset = read_csv(&csv_path, &columns).unwrap();
+        dataset.sort_by_timestamp();
+        dataset.validate().unwrap();
+        assert_eq!(dataset.feature_names, ["f1", "f2"]);
+        assert_eq!(dataset.mid_prices, [100.0, 100.5]);
+        assert_eq!(dataset.features[0], [0.5, 1.0]);
+
+        dataset.symbol = Some("NQ".to_string());
+        binary::write(&bin_path, &dataset).unwrap();
+        assert!(binary::has_header(&bin_path).unwrap());
+        let header = binary::read_header(&bin_path).unwrap();
+        assert_eq!(header.n_rows, 2);
+        assert_eq!(header.end_timestamp, 1_704_067_201_000_000_000);
+        let restored = binary::read(&bin_path).unwrap();
+        assert_eq!(restored.timestamps, dataset.timestamps);
+        assert_eq!(restored.features[0], dataset.features[0]);
+        assert!(restored.features[1].iter().all(|x| x.is_nan()));
+        assert_eq!(restored.symbol.as_deref(), Some("NQ"));
+
+        let mut bytes = std::fs::read(&bin_path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        std::fs::write(&bin_path, &bytes).unwrap();
+        let error = binary::read(&bin_path).unwrap_err();
+        assert!(format!("{:#}", error).contains("checksum"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1 +1,128 @@
+pub mod binary;
+mod csv_reader;
 pub mod nq_f1_f2_f4;
+mod raw_dataset;
+mod resample;
+mod roll;
+pub mod synthetic;
+
+use std::sync::Arc;
+
+use crate::config::{FeatureConfig, ResampleConfig, RollConfig};
+use crate::dtypes::{Dataset, DatasetRef, Price};
+use crate::features::{build_features, FeaturePoint};
+use crate::utils;
+
+pub use csv_reader::{parse_timestamp, read_csv, CsvColumns};
+pub use raw_dataset::RawDataset;
+pub use resample::resample;
+pub use roll::{
+    adjust_prices, contracts_from_config, contracts_from_rows, roll_timestamps, ContractSpan,
+};
+
+/// Reads a dataset file, with a header or in the headerless `nq_f1_f2_f4` layout.
+/// Files with a header are verified against it.
+pub fn read(path: &str) -> anyhow::Result<RawDataset> {
+    let path: String = utils::canonicalize_path(path)?;
+    if binary::has_header(&path)? {
+        binary::read(&path)
+    } else {
+        let mut dataset = RawDataset::from_dataset(&nq_f1_f2_f4::read(&path)?.into());
+        dataset.symbol = Some("NQ".to_string());
+        Ok(dataset)
+    }
+}
+
+/// Contracts of the dataset, listed in the roll config or else recorded in the file header.
+pub fn contracts(path: &str, roll_config: &RollConfig) -> anyhow::Result<Vec<ContractSpan>> {
+    if !roll_config.contracts.is_empty() {
+        return contracts_from_config(roll_config);
+    }
+    let path: String = utils::canonicalize_path(path)?;
+    if binary::has_header(&path)? {
+        Ok(binary::read_header(&path)?.contracts)
+    } else {
+        Ok(vec![])
+    }
+}
+
+/// Loads the dataset, optionally roll-adjusted and resampled, with `features` appended
+/// to the points. Features are computed on the whole file, so that history before
+/// `offset` is used.
+pub fn load_with_features(
+    path: &str,
+    offset: f64,
+    limit: f64,
+    features: &[FeatureConfig],
+    resample_config: Option<&ResampleConfig>,
+    roll_config: Option<&RollConfig>,
+) -> anyhow::Result<DatasetRef<FeaturePoint>> {
+    load(
+        path,
+        offset,
+        limit,
+        features,
+        resample_config,
+        roll_config,
+        false,
+    )
+}
+
+/// Loads the dataset like `load_with_features`, but with the raw prices of each contract
+/// in the bars, the features still being computed on the roll-adjusted prices. Backtests
+/// close and reopen positions at rolls, so adjusted prices would only distort their
+/// profits, e.g. scaled by ratio adjustment.
+pub fn load_with_features_at_raw_prices(
+    path: &str,
+    offset: f64,
+    limit: f64,
+    features: &[FeatureConfig],
+    resample_config: Option<&ResampleConfig>,
+    roll_config: Option<&RollConfig>,
+) -> anyhow::Result<DatasetRef<FeaturePoint>> {
+    load(
+        path,
+        offset,
+        limit,
+        features,
+        resample_config,
+        roll_config,
+        true,
+    )
+}
+
+fn load(
+    path: &str,
+    offset: f64,
+    limit: f64,
+    features: &[FeatureConfig],
+    resample_config: Option<&ResampleConfig>,
+    roll_config: Option<&RollConfig>,
+    raw_prices: bool,
+) -> anyhow::Result<DatasetRef<FeaturePoint>> {
+    let mut raw: RawDataset = read(path)?;
+    let mut adjusted: Option<RawDataset> = None;
+    if let Some(roll_config) = roll_config {
+        if !roll_config.contracts.is_empty() {
+            raw.contracts = contracts_from_config(roll_config)?;
+            raw.validate()?;
+        }
+        let mut dataset: RawDataset = raw.clone();
+        adjust_prices(&mut dataset, roll_config.adjustment)?;
+        adjusted = Some(dataset);
+    }
+    if let Some(resample_config) = resample_config {
+        raw = resample(&raw, resample_config)?;
+        adjusted = adjusted
+            .map(|dataset| resample(&dataset, resample_config))
+            .transpose()?;
+    }
+    let mut dataset: Dataset<FeaturePoint> =
+        build_features(adjusted.as_ref().unwrap_or(&raw), features)?;
+    if raw_prices {
+        for (bar, price) in dataset.bars.iter_mut().zip(raw.mid_prices.iter()) {
+            bar.mid_price = Price(*price);
+        }
+    }
+    Ok(Arc::new(dataset.slice(offset, limit)))
+}
@@ -1,9 +1,11 @@
-use crate::dtypes::{Bar, Price};
+use anyhow::anyhow;
+
+use crate::dtypes::{Bar, Dataset, Price};
 use crate::{dtypes, utils};
 use std::io::Read;
 use std::sync::Arc;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, dtypes::Point)]
 #[repr(C)]
 pub struct Point {
     pub f1: f64,
@@ -11,10 +13,6 @@ pub struct Point {
     pub f4: f64,
 }
 
-impl dtypes::Point for Point {}
-unsafe impl Sync for Point {}
-unsafe impl Send for Point {}
-
 #[repr(C)]
 struct Row {
     mp: f64,
@@ -25,10 +23,24 @@ struct Row {
 }
 
 pub fn load(path: &str, offset: f64, limit: f64) -> dtypes::DatasetRef<Point> {
-    let path = utils::canonicalize_path(path).expect("failed to canonicalize path");
-    let file = std::fs::File::open(path).expect("failed to open file");
-    let file_size = file.metadata().expect("failed to get metadata").len();
+    let dataset: Dataset<Point> = read(path).expect("failed to read dataset").into();
+    Arc::new(dataset.slice(offset, limit))
+}
+
+/// Reads the headerless layout, an array of `Row`.
+pub(crate) fn read(path: &str) -> anyhow::Result<Vec<Bar<Point>>> {
+    let path = utils::canonicalize_path(path)?;
+    let file = std::fs::File::open(&path)?;
+    let file_size = file.metadata()?.len();
     let row_size = std::mem::size_of::<Row>() as u64;
+    if file_size % row_size != 0 {
+        return Err(anyhow!(
+            "{} has no dataset header and its size {} is not a multiple of the {}-byte row",
+            path,
+            file_size,
+            row_size
+        ));
+    }
     let n_rows = file_size / row_size;
     let mut dataset: Vec<Bar<Point>> = Vec::with_capacity(n_rows as usize);
     let mut row: Row = unsafe { std::mem::zeroed() };
@@ -36,7 +48,7 @@ pub fn load(path: &str, offset: f64, limit: f64) -> dtypes::DatasetRef<Point> {
         std::slice::from_raw_parts_mut(&mut row as *mut Row as *mut u8, std::mem::size_of::<Row>())
     };
     let mut reader = std::io::BufReader::new(file);
-    while let Ok(_) = reader.read_exact(row_slice) {
+    while reader.read_exact(row_slice).is_ok() {
         let bar = Bar {
             timestamp: row.ts,
             mid_price: Price(row.mp),
@@ -48,8 +60,5 @@ pub fn load(path: &str, offset: f64, limit: f64) -> dtypes::DatasetRef<Point> {
         };
         dataset.push(bar);
     }
-    let offset = (offset * n_rows as f64) as usize;
-    let limit = (limit * n_rows as f64) as usize;
-    let dataset = dataset[offset..offset + limit].to_vec();
-    Arc::new(dataset)
+    Ok(dataset)
 }
@@ -0,0 +1,109 @@
+use anyhow::anyhow;
+
+use crate::datasets::ContractSpan;
+use crate::dtypes::{Dataset, Point};
+
+/// Columns of a dataset as read from a file, before features are derived.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RawDataset {
+    pub timestamps: Vec<i64>,
+    pub mid_prices: Vec<f64>,
+    pub feature_names: Vec<String>,
+    /// `[n_features][n_rows]`
+    pub features: Vec<Vec<f64>>,
+    /// Instrument the bars are of, if known
+    pub symbol: Option<String>,
+    /// Futures contracts by start timestamp, empty if unknown
+    pub contracts: Vec<ContractSpan>,
+}
+
+impl RawDataset {
+    pub fn from_dataset<T: Point>(dataset: &Dataset<T>) -> Self {
+        Self {
+            timestamps: dataset.iter().map(|bar| bar.timestamp).collect(),
+            mid_prices: dataset.iter().map(|bar| bar.mid_price.0).collect(),
+            feature_names: dataset.feature_names.clone(),
+            features: (0..dataset.n_features())
+                .map(|i| dataset.iter().map(|bar| bar.point.as_slice()[i]).collect())
+                .collect(),
+            symbol: None,
+            contracts: vec![],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    /// Sorts rows by timestamp, keeping the order of equal timestamps.
+    pub fn sort_by_timestamp(&mut self) {
+        if self.timestamps.windows(2).all(|w| w[0] <= w[1]) {
+            return;
+        }
+        let mut order: Vec<usize> = (0..self.len()).collect();
+        order.sort_by_key(|&i| self.timestamps[i]);
+        self.timestamps = order.iter().map(|&i| self.timestamps[i]).collect();
+        self.mid_prices = order.iter().map(|&i| self.mid_prices[i]).collect();
+        for column in self.features.iter_mut() {
+            *column = order.iter().map(|&i| column[i]).collect();
+        }
+    }
+
+    /// Checks column lengths, names, sorted unique timestamps, positive finite prices
+    /// and contracts with distinct codes and increasing start timestamps.
+    /// Features may be NaN, e.g. before enough history is available.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.is_empty() {
+            return Err(anyhow!("dataset is empty"));
+        }
+        if self.mid_prices.len() != self.len()
+            || self.features.len() != self.feature_names.len()
+            || self
+                .features
+                .iter()
+                .any(|column| column.len() != self.len())
+        {
+            return Err(anyhow!("dataset columns have different lengths"));
+        }
+        for (i, name) in self.feature_names.iter().enumerate() {
+            if name.is_empty() || self.feature_names[..i].contains(name) {
+                return Err(anyhow!("empty or duplicate feature name {:?}", name));
+            }
+        }
+        if let Some(i) = (1..self.len()).find(|&i| self.timestamps[i] <= self.timestamps[i - 1]) {
+            return Err(anyhow!(
+                "timestamps are not strictly increasing at row {}: {} after {}",
+                i,
+                self.timestamps[i],
+                self.timestamps[i - 1]
+            ));
+        }
+        if let Some(i) = self
+            .mid_prices
+            .iter()
+            .position(|p| !(p.is_finite() && *p > 0.0))
+        {
+            return Err(anyhow!(
+                "invalid mid price {} at row {}",
+                self.mid_prices[i],
+                i
+            ));
+        }
+        for (i, contract) in self.contracts.iter().enumerate() {
+            if contract.code.is_empty()
+                || self.contracts[..i].iter().any(|c| c.code == contract.code)
+                || (i > 0 && contract.start_timestamp <= self.contracts[i - 1].start_timestamp)
+            {
+                return Err(anyhow!(
+                    "contract {:?} is repeated or does not start after the previous one",
+                    contract.code
+                ));
+            }
+        }
+        Ok(())
+    }
+}
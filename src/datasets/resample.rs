@@ -0,0 +1,241 @@
+use anyhow::anyhow;
+
+use std::ops::Range;
+
+use crate::config::{Aggregation, PriceAggregation, ResampleConfig};
+use crate::datasets::roll::roll_indices;
+use crate::datasets::RawDataset;
+
+/// Aggregates groups of consecutive rows into one row, timestamped by the last row of the group.
+/// Groups end at contract rolls, so that no row mixes the prices of two contracts.
+pub fn resample(dataset: &RawDataset, config: &ResampleConfig) -> anyhow::Result<RawDataset> {
+    let groups: Vec<Range<usize>> = match (config.every_bars, config.interval_secs) {
+        (Some(n), None) if n > 0 => (0..dataset.len())
+            .step_by(n)
+            .map(|start| start..(start + n).min(dataset.len()))
+            .collect(),
+        (None, Some(secs)) if secs > 0 => {
+            let interval: i64 = secs as i64 * 1_000_000_000;
+            let mut groups = Vec::new();
+            let mut start: usize = 0;
+            for i in 1..=dataset.len() {
+                if i == dataset.len()
+                    || dataset.timestamps[i].div_euclid(interval)
+                        != dataset.timestamps[start].div_euclid(interval)
+                {
+                    groups.push(start..i);
+                    start = i;
+                }
+            }
+            groups
+        }
+        _ => {
+            return Err(anyhow!(
+                "resample needs exactly one of positive every_bars and interval_secs"
+            ))
+        }
+    };
+    let groups = split_at(
+        groups,
+        &roll_indices(&dataset.timestamps, &dataset.contracts),
+    );
+    let volumes: Option<&[f64]> = match config.mid_price {
+        PriceAggregation::Vwap => {
+            let name = config
+                .volume_feature
+                .as_ref()
+                .ok_or_else(|| anyhow!("vwap mid price needs volume_feature"))?;
+            let index = dataset
+                .feature_names
+                .iter()
+                .position(|n| n == name)
+                .ok_or_else(|| anyhow!("unknown volume feature {:?}", name))?;
+            Some(&dataset.features[index])
+        }
+        _ => None,
+    };
+    if let Some(name) = config
+        .features
+        .keys()
+        .find(|name| !dataset.feature_names.contains(name))
+    {
+        return Err(anyhow!(
+            "aggregation configured for unknown feature {:?}",
+            name
+        ));
+    }
+    let mid_prices: Vec<f64> = groups
+        .iter()
+        .map(|group| {
+            let prices = &dataset.mid_prices[group.clone()];
+            match (config.mid_price, volumes) {
+                (PriceAggregation::Vwap, Some(volumes)) => {
+                    let volumes = &volumes[group.clone()];
+                    let total: f64 = volumes.iter().filter(|v| v.is_finite()).sum();
+                    if total > 0.0 {
+                        prices
+                            .iter()
+                            .zip(volumes)
+                            .filter(|(_, v)| v.is_finite())
+                            .map(|(p, v)| p * v)
+                            .sum::<f64>()
+                            / total
+                    } else {
+                        prices[prices.len() - 1]
+                    }
+                }
+                (PriceAggregation::Mean, _) => aggregate(prices, Aggregation::Mean),
+                _ => prices[prices.len() - 1],
+            }
+        })
+        .collect();
+    let features: Vec<Vec<f64>> = dataset
+        .feature_names
+        .iter()
+        .zip(dataset.features.iter())
+        .map(|(name, column)| {
+            let aggregation = *config
+                .features
+                .get(name)
+                .unwrap_or(&config.default_aggregation);
+            groups
+                .iter()
+                .map(|group| aggregate(&column[group.clone()], aggregation))
+                .collect()
+        })
+        .collect();
+    Ok(RawDataset {
+        timestamps: groups
+            .iter()
+            .map(|g| dataset.timestamps[g.end - 1])
+            .collect(),
+        mid_prices,
+        feature_names: dataset.feature_names.clone(),
+        features,
+        symbol: dataset.symbol.clone(),
+        contracts: dataset.contracts.clone(),
+    })
+}
+
+/// Splits the groups holding any of the sorted row `indices` after their first row,
+/// so that each index starts a group.
+fn split_at(groups: Vec<Range<usize>>, indices: &[usize]) -> Vec<Range<usize>> {
+    let mut split: Vec<Range<usize>> = Vec::with_capacity(groups.len() + indices.len());
+    for group in groups {
+        let mut start: usize = group.start;
+        for &index in indices
+            .iter()
+            .filter(|&&i| i > group.start && i < group.end)
+        {
+            split.push(start..index);
+            start = index;
+        }
+        split.push(start..group.end);
+    }
+    split
+}
+
+fn aggregate(values: &[f64], aggregation: Aggregation) -> f64 {
+    let finite = values.iter().copied().filter(|v| !v.is_nan());
+    match aggregation {
+        Aggregation::Last => values[values.len() - 1],
+        Aggregation::Mean => {
+            let (sum, n) = finite.fold((0.0, 0usize), |(sum, n), v| (sum + v, n + 1));
+            if n > 0 {
+                sum / n as f64
+            } else {
+                f64::NAN
+            }
+        }
+        Aggregation::Min => finite.fold(f64::NAN, f64::min),
+        Aggregation::Max => finite.fold(f64::NAN, f64::max),
+        Aggregation::Sum => finite.sum(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::datasets::ContractSpan;
+
+    #[test]
+    fn test_resample() {
+        let dataset = RawDataset {
+            timestamps: (0..5).map(|i| i * 30_000_000_000).collect(),
+            mid_prices: vec![100.0, 101.0, 102.0, 103.0, 104.0],
+            feature_names: vec!["x".to_string(), "volume".to_string()],
+            features: vec![
+                vec![1.0, f64::NAN, 3.0, 4.0, 5.0],
+                vec![1.0, 3.0, 1.0, 1.0, 1.0],
+            ],
+            symbol: None,
+            contracts: vec![],
+        };
+        let mut config = ResampleConfig {
+            every_bars: None,
+            interval_secs: Some(60),
+            mid_price: PriceAggregation::Vwap,
+            volume_feature: Some("volume".to_string()),
+            default_aggregation: Aggregation::Sum,
+            features: [("x".to_string(), Aggregation::Max)].into_iter().collect(),
+        };
+        let resampled = resample(&dataset, &config).unwrap();
+        assert_eq!(
+            resampled.timestamps,
+            [30_000_000_000, 90_000_000_000, 120_000_000_000]
+        );
+        assert_eq!(resampled.mid_prices, [100.75, 102.5, 104.0]);
+        assert_eq!(resampled.features[0], [1.0, 4.0, 5.0]);
+        assert_eq!(resampled.features[1], [4.0, 2.0, 1.0]);
+
+        config.interval_secs = None;
+        config.every_bars = Some(2);
+        config.mid_price = PriceAggregation::Last;
+        config.features.insert("x".to_string(), Aggregation::Mean);
+        let resampled = resample(&dataset, &config).unwrap();
+        assert_eq!(resampled.mid_prices, [101.0, 103.0, 104.0]);
+        assert_eq!(resampled.features[0], [1.0, 3.5, 5.0]);
+    }
+
+    #[test]
+    fn test_resample_splits_at_rolls() {
+        // a new contract starts at the fourth row, 50 points above the first one
+        let dataset = RawDataset {
+            timestamps: (0..5).map(|i| i * 30_000_000_000).collect(),
+            mid_prices: vec![100.0, 101.0, 102.0, 153.0, 154.0],
+            feature_names: vec!["x".to_string()],
+            features: vec![vec![1.0, 2.0, 3.0, 4.0, 5.0]],
+            symbol: None,
+            contracts: vec![
+                ContractSpan {
+                    code: "A".to_string(),
+                    start_timestamp: 0,
+                },
+                ContractSpan {
+                    code: "B".to_string(),
+                    start_timestamp: 90_000_000_000,
+                },
+            ],
+        };
+        let config = ResampleConfig {
+            every_bars: None,
+            interval_secs: Some(60),
+            mid_price: PriceAggregation::Mean,
+            volume_feature: None,
+            default_aggregation: Aggregation::Mean,
+            features: Default::default(),
+        };
+        let resampled = resample(&dataset, &config).unwrap();
+        assert_eq!(
+            resampled.timestamps,
+            [
+                30_000_000_000,
+                60_000_000_000,
+                90_000_000_000,
+                120_000_000_000
+            ]
+        );
+        assert_eq!(resampled.mid_prices, [100.5, 102.0, 153.0, 154.0]);
+        assert_eq!(resampled.features[0], [1.5, 3.0, 4.0, 5.0]);
+    }
+}
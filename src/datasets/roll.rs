@@ -0,0 +1,151 @@
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{PriceAdjustment, RollConfig};
+use crate::datasets::{parse_timestamp, RawDataset};
+
+/// Futures contract whose prices a dataset uses from `start_timestamp`
+/// until the start of the next contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractSpan {
+    pub code: String,
+    pub start_timestamp: i64,
+}
+
+/// Contracts listed in the roll config.
+pub fn contracts_from_config(config: &RollConfig) -> anyhow::Result<Vec<ContractSpan>> {
+    config
+        .contracts
+        .iter()
+        .map(|contract| {
+            Ok(ContractSpan {
+                code: contract.code.clone(),
+                start_timestamp: parse_timestamp(&contract.start)
+                    .map_err(|e| e.context(format!("contract {}", contract.code)))?,
+            })
+        })
+        .collect()
+}
+
+/// Contract spans of rows with the given timestamps and contract codes, in any order.
+/// Each contract must cover a contiguous range of timestamps.
+pub fn contracts_from_rows(
+    timestamps: &[i64],
+    codes: &[String],
+) -> anyhow::Result<Vec<ContractSpan>> {
+    let mut rows: Vec<(i64, &String)> = timestamps.iter().copied().zip(codes).collect();
+    rows.sort_by_key(|(timestamp, _)| *timestamp);
+    let mut contracts: Vec<ContractSpan> = Vec::new();
+    for (timestamp, code) in rows {
+        if contracts.last().map(|c| &c.code) == Some(code) {
+            continue;
+        }
+        if contracts.iter().any(|c| &c.code == code) {
+            return Err(anyhow!(
+                "contract {} appears again at timestamp {} after a roll",
+                code,
+                timestamp
+            ));
+        }
+        contracts.push(ContractSpan {
+            code: code.clone(),
+            start_timestamp: timestamp,
+        });
+    }
+    Ok(contracts)
+}
+
+/// Start timestamps of all contracts but the first, i.e. when positions are rolled.
+pub fn roll_timestamps(contracts: &[ContractSpan]) -> Vec<i64> {
+    contracts
+        .iter()
+        .skip(1)
+        .map(|c| c.start_timestamp)
+        .collect()
+}
+
+/// Indices of the first rows of each contract after the first one, excluding row 0.
+pub fn roll_indices(timestamps: &[i64], contracts: &[ContractSpan]) -> Vec<usize> {
+    roll_timestamps(contracts)
+        .into_iter()
+        .map(|start| timestamps.partition_point(|&t| t < start))
+        .filter(|&index| index > 0 && index < timestamps.len())
+        .collect()
+}
+
+/// Removes the price gaps at rolls by shifting or scaling the prices of earlier contracts,
+/// so that the latest contract keeps its prices. The gap is measured between the last
+/// bar of a contract and the first bar of the next one.
+pub fn adjust_prices(dataset: &mut RawDataset, adjustment: PriceAdjustment) -> anyhow::Result<()> {
+    let rolls: Vec<usize> = roll_indices(&dataset.timestamps, &dataset.contracts);
+    let raw: Vec<f64> = dataset.mid_prices.clone();
+    let prices: &mut Vec<f64> = &mut dataset.mid_prices;
+    match adjustment {
+        PriceAdjustment::None => {}
+        PriceAdjustment::Back => {
+            let mut offset: f64 = 0.0;
+            for index in (0..prices.len()).rev() {
+                if rolls.contains(&(index + 1)) {
+                    offset += raw[index + 1] - raw[index];
+                }
+                prices[index] += offset;
+            }
+            if let Some(i) = prices.iter().position(|p| *p <= 0.0) {
+                return Err(anyhow!(
+                    "back-adjusted mid price {} at row {} is not positive, use ratio adjustment",
+                    prices[i],
+                    i
+                ));
+            }
+        }
+        PriceAdjustment::Ratio => {
+            let mut factor: f64 = 1.0;
+            for index in (0..prices.len()).rev() {
+                if rolls.contains(&(index + 1)) {
+                    factor *= raw[index + 1] / raw[index];
+                }
+                prices[index] *= factor;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_adjust_prices() {
+        let codes: Vec<String> = ["NQH4", "NQH4", "NQM4", "NQM4", "NQU4"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let timestamps: Vec<i64> = vec![0, 1, 2, 3, 4];
+        let contracts = contracts_from_rows(&timestamps, &codes).unwrap();
+        assert_eq!(roll_timestamps(&contracts), [2, 4]);
+        assert_eq!(roll_indices(&[0, 3, 5], &contracts), [1, 2]);
+        let mut dataset = RawDataset {
+            timestamps,
+            mid_prices: vec![100.0, 101.0, 111.0, 112.0, 124.0],
+            contracts,
+            ..RawDataset::default()
+        };
+        let raw = dataset.clone();
+        adjust_prices(&mut dataset, PriceAdjustment::Back).unwrap();
+        assert_eq!(dataset.mid_prices, [122.0, 123.0, 123.0, 124.0, 124.0]);
+        dataset.mid_prices = raw.mid_prices.clone();
+        adjust_prices(&mut dataset, PriceAdjustment::Ratio).unwrap();
+        let (late, early) = (124.0 / 112.0, 124.0 / 112.0 * 111.0 / 101.0);
+        let expected = [100.0 * early, 101.0 * early, 111.0 * late, 124.0, 124.0];
+        for (price, expected) in dataset.mid_prices.iter().zip(expected) {
+            assert!((price - expected).abs() < 1e-9);
+        }
+
+        let interleaved: Vec<String> = ["NQH4", "NQM4", "NQH4"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(contracts_from_rows(&[0, 1, 2], &interleaved).is_err());
+    }
+}
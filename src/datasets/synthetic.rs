@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::dtypes::{Bar, DatasetRef, Point, Price};
+
+/// Features of synthetic bars: `signal` depends on the process, `noise` is independent N(0, 1).
+#[derive(Debug, Clone, Point)]
+#[repr(C)]
+pub struct SyntheticPoint {
+    pub signal: f64,
+    pub noise: f64,
+}
+
+/// Price process of a synthetic market. Volatilities are per bar, in bps of log price.
+#[derive(Debug, Clone)]
+pub enum Process {
+    /// Driftless random walk; `signal` is noise
+    RandomWalk { volatility_bps: f64 },
+    /// Log price reverts to the start price at rate `mean_reversion` per bar;
+    /// `signal` is the standardized deviation below the mean
+    OrnsteinUhlenbeck {
+        mean_reversion: f64,
+        volatility_bps: f64,
+    },
+    /// Drift of `drift_bps` per bar flips sign with `switch_probability` per bar;
+    /// `signal` is the standardized return over the last 20 bars
+    RegimeSwitching {
+        drift_bps: f64,
+        volatility_bps: f64,
+        switch_probability: f64,
+    },
+    /// `signal` is a persistent AR(1) factor, and the next return is
+    /// `volatility_bps * (signal_to_noise * signal + noise)`
+    PlantedSignal {
+        volatility_bps: f64,
+        signal_to_noise: f64,
+        persistence_bars: f64,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntheticConfig {
+    pub process: Process,
+    pub n_bars: usize,
+    pub start_price: f64,
+    pub bar_secs: i64,
+    pub seed: u64,
+}
+
+const TRAILING_BARS: usize = 20;
+
+/// Generates a reproducible dataset for the given seed.
+pub fn generate(config: &SyntheticConfig) -> DatasetRef<SyntheticPoint> {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let start_log_price: f64 = config.start_price.ln();
+    let mut log_prices: Vec<f64> = Vec::with_capacity(config.n_bars);
+    let mut signals: Vec<f64> = Vec::with_capacity(config.n_bars);
+    let mut log_price: f64 = start_log_price;
+    let mut factor: f64 = 0.0;
+    let mut regime: f64 = 1.0;
+    for t in 0..config.n_bars {
+        let shock: f64 = standard_normal(&mut rng);
+        let signal: f64 = match config.process {
+            Process::RandomWalk { .. } => standard_normal(&mut rng),
+            Process::OrnsteinUhlenbeck {
+                mean_reversion,
+                volatility_bps,
+            } => {
+                let stationary_std =
+                    volatility_bps * 1e-4 / (mean_reversion * (2.0 - mean_reversion)).sqrt();
+                (start_log_price - log_price) / stationary_std
+            }
+            Process::RegimeSwitching { volatility_bps, .. } => match t.checked_sub(TRAILING_BARS) {
+                Some(s) => {
+                    (log_price - log_prices[s])
+                        / (volatility_bps * 1e-4 * (TRAILING_BARS as f64).sqrt())
+                }
+                None => f64::NAN,
+            },
+            Process::PlantedSignal {
+                persistence_bars, ..
+            } => {
+                let phi: f64 = 1.0 - 1.0 / persistence_bars.max(1.0);
+                factor = phi * factor + (1.0 - phi * phi).sqrt() * standard_normal(&mut rng);
+                factor
+            }
+        };
+        log_prices.push(log_price);
+        signals.push(signal);
+        let step_bps: f64 = match config.process {
+            Process::RandomWalk { volatility_bps } => volatility_bps * shock,
+            Process::OrnsteinUhlenbeck {
+                mean_reversion,
+                volatility_bps,
+            } => (start_log_price - log_price) * mean_reversion * 1e4 + volatility_bps * shock,
+            Process::RegimeSwitching {
+                drift_bps,
+                volatility_bps,
+                switch_probability,
+            } => {
+                if rng.gen_bool(switch_probability) {
+                    regime = -regime;
+                }
+                regime * drift_bps + volatility_bps * shock
+            }
+            Process::PlantedSignal {
+                volatility_bps,
+                signal_to_noise,
+                ..
+            } => volatility_bps * (signal_to_noise * factor + shock),
+        };
+        log_price += step_bps * 1e-4;
+    }
+    let bars: Vec<Bar<SyntheticPoint>> = (0..config.n_bars)
+        .map(|t| Bar {
+            timestamp: t as i64 * config.bar_secs * 1_000_000_000,
+            mid_price: Price(log_prices[t].exp()),
+            point: SyntheticPoint {
+                signal: signals[t],
+                noise: standard_normal(&mut rng),
+            },
+        })
+        .collect();
+    Arc::new(bars.into())
+}
+
+/// Box-Muller transform
+fn standard_normal(rng: &mut impl Rng) -> f64 {
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen::<f64>();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
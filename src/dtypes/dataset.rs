@@ -0,0 +1,58 @@
+use std::ops::{Deref, Range};
+
+use crate::dtypes::{Bar, Point, StaticPoint};
+
+/// Bars with the names of the features of their points, in slice order.
+/// Derefs to the bars.
+#[derive(Debug, Clone)]
+pub struct Dataset<T: Point> {
+    pub feature_names: Vec<String>,
+    pub bars: Vec<Bar<T>>,
+}
+
+impl<T: Point> Dataset<T> {
+    pub fn new(feature_names: Vec<String>, bars: Vec<Bar<T>>) -> Self {
+        Self {
+            feature_names,
+            bars,
+        }
+    }
+
+    pub fn n_features(&self) -> usize {
+        self.feature_names.len()
+    }
+
+    /// Copy of the bars in `range`, with the same features
+    pub fn range(&self, range: Range<usize>) -> Self {
+        Self::new(self.feature_names.clone(), self.bars[range].to_vec())
+    }
+
+    /// Copy of the bars from `offset` on for `limit`, both fractions of the length
+    pub fn slice(&self, offset: f64, limit: f64) -> Self {
+        let n_rows = self.len();
+        let offset = (offset * n_rows as f64) as usize;
+        let limit = (limit * n_rows as f64) as usize;
+        self.range(offset..offset + limit)
+    }
+}
+
+impl<T: StaticPoint> From<Vec<Bar<T>>> for Dataset<T> {
+    fn from(bars: Vec<Bar<T>>) -> Self {
+        let feature_names = T::feature_names().iter().map(|s| s.to_string()).collect();
+        Self::new(feature_names, bars)
+    }
+}
+
+impl<T: StaticPoint> FromIterator<Bar<T>> for Dataset<T> {
+    fn from_iter<I: IntoIterator<Item = Bar<T>>>(bars: I) -> Self {
+        bars.into_iter().collect::<Vec<_>>().into()
+    }
+}
+
+impl<T: Point> Deref for Dataset<T> {
+    type Target = [Bar<T>];
+
+    fn deref(&self) -> &[Bar<T>] {
+        &self.bars
+    }
+}
@@ -1,15 +1,18 @@
 mod bar;
+mod dataset;
 mod point;
 mod price;
 mod sample;
 mod utility;
 
 pub use bar::Bar;
-pub use point::Point;
+pub use cfr_derive::Point;
+pub use dataset::Dataset;
+pub use point::{Point, StaticPoint};
 pub use price::Price;
 pub use sample::Sample;
 pub use utility::Utility;
 
 use std::sync::Arc;
 
-pub type DatasetRef<T> = Arc<Vec<Bar<T>>>;
+pub type DatasetRef<T> = Arc<Dataset<T>>;
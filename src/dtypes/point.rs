@@ -1,26 +1,45 @@
-/// Array of f64 features at a point of time
+/// Array of f64 features at a point of time.
+/// Implement it with `#[derive(Point)]` on a `#[repr(C)]` struct of `f64` fields,
+/// or use `FeaturePoint` for features configured at runtime.
+/// Feature names are carried by the `Dataset` of the points.
 pub trait Point
 where
     Self: Sized + Clone + Send + Sync + std::fmt::Debug + 'static,
 {
-    fn as_ref(&self) -> &[f64] {
-        unsafe {
-            std::slice::from_raw_parts(
-                self as *const Self as *const f64,
-                std::mem::size_of::<Self>(),
-            )
-        }
-    }
+    fn as_slice(&self) -> &[f64];
+
+    /// Panics if `values.len()` differs from the number of features of a `StaticPoint`
+    fn from_slice(values: &[f64]) -> Self;
 
-    fn size() -> usize {
-        std::mem::size_of::<Self>()
+    fn is_finite(&self) -> bool {
+        self.as_slice().iter().all(|x| x.is_finite())
     }
+}
 
-    fn default() -> Self {
-        unsafe { std::mem::zeroed() }
+/// Point with features fixed at compile time, implemented by `#[derive(Point)]`.
+pub trait StaticPoint: Point {
+    /// Names of the features in slice order
+    fn feature_names() -> &'static [&'static str];
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dtypes::{Point, StaticPoint};
+
+    #[derive(Debug, Clone, PartialEq, Point)]
+    #[repr(C)]
+    struct TestPoint {
+        price_return: f64,
+        volume: f64,
     }
 
-    fn is_finite(&self) -> bool {
-        self.as_ref().iter().all(|x| x.is_finite())
+    #[test]
+    fn test_derive() {
+        assert_eq!(TestPoint::feature_names(), &["price_return", "volume"]);
+        let point = TestPoint::from_slice(&[0.5, f64::NAN]);
+        assert_eq!(point.as_slice()[0], 0.5);
+        assert_eq!(point.as_slice().len(), 2);
+        assert!(!point.is_finite());
+        assert!(TestPoint::from_slice(&[0.0, 0.0]).is_finite());
     }
 }
@@ -0,0 +1,251 @@
+use anyhow::anyhow;
+
+use crate::config::FeatureConfig;
+use crate::datasets::RawDataset;
+use crate::dtypes::{Bar, Dataset, Price};
+use crate::features::FeaturePoint;
+
+const MID_PRICE: &str = "mid_price";
+const NANOS_PER_DAY: i64 = 86_400_000_000_000;
+
+/// Makes bars of the dataset rows, extending the features with ones derived from
+/// prices, timestamps and other features. Features are appended in config order,
+/// so a feature may be computed from one configured before it. Values are NaN
+/// until enough history is available.
+pub fn build_features(
+    dataset: &RawDataset,
+    configs: &[FeatureConfig],
+) -> anyhow::Result<Dataset<FeaturePoint>> {
+    let mut columns = Columns {
+        names: dataset.feature_names.clone(),
+        values: dataset.features.clone(),
+        mid_price: dataset.mid_prices.clone(),
+    };
+    let timestamps: &[i64] = &dataset.timestamps;
+    for config in configs {
+        match config {
+            FeatureConfig::Return { bars: n } => {
+                let log_price: Vec<f64> = columns.mid_price.iter().map(|p| p.ln()).collect();
+                let values = (0..log_price.len())
+                    .map(|t| match t.checked_sub(*n) {
+                        Some(s) => log_price[t] - log_price[s],
+                        None => f64::NAN,
+                    })
+                    .collect();
+                columns.push(format!("return_{}", n), values)?;
+            }
+            FeatureConfig::ZScore { source, window } => {
+                let source_values = columns.get(source)?;
+                let values = rolling(source_values, *window)
+                    .into_iter()
+                    .zip(source_values)
+                    .map(|((mean, std), x)| {
+                        if std > 0.0 {
+                            (x - mean) / std
+                        } else {
+                            f64::NAN
+                        }
+                    })
+                    .collect();
+                columns.push(format!("z_score_{}_{}", source, window), values)?;
+            }
+            FeatureConfig::Ewma { source, span } => {
+                let alpha: f64 = 2.0 / (*span as f64 + 1.0);
+                let mut ewma: f64 = f64::NAN;
+                let values = columns
+                    .get(source)?
+                    .iter()
+                    .map(|&x| {
+                        if x.is_finite() {
+                            ewma = if ewma.is_nan() {
+                                x
+                            } else {
+                                ewma + alpha * (x - ewma)
+                            };
+                        }
+                        ewma
+                    })
+                    .collect();
+                columns.push(format!("ewma_{}_{}", source, span), values)?;
+            }
+            FeatureConfig::Volatility { window } => {
+                let returns: Vec<f64> = (0..columns.mid_price.len())
+                    .map(|t| match t {
+                        0 => f64::NAN,
+                        _ => (columns.mid_price[t] / columns.mid_price[t - 1]).ln(),
+                    })
+                    .collect();
+                let values = rolling(&returns, *window)
+                    .into_iter()
+                    .map(|(_, std)| std)
+                    .collect();
+                columns.push(format!("volatility_{}", window), values)?;
+            }
+            FeatureConfig::TimeOfDay => {
+                let phase: Vec<f64> = timestamps
+                    .iter()
+                    .map(|ts| {
+                        let day_fraction =
+                            ts.rem_euclid(NANOS_PER_DAY) as f64 / NANOS_PER_DAY as f64;
+                        2.0 * std::f64::consts::PI * day_fraction
+                    })
+                    .collect();
+                columns.push(
+                    "time_of_day_sin".to_string(),
+                    phase.iter().map(|p| p.sin()).collect(),
+                )?;
+                columns.push(
+                    "time_of_day_cos".to_string(),
+                    phase.iter().map(|p| p.cos()).collect(),
+                )?;
+            }
+            FeatureConfig::Lag { source, bars: n } => {
+                let source_values = columns.get(source)?;
+                let values = (0..source_values.len())
+                    .map(|t| match t.checked_sub(*n) {
+                        Some(s) => source_values[s],
+                        None => f64::NAN,
+                    })
+                    .collect();
+                columns.push(format!("lag_{}_{}", source, n), values)?;
+            }
+        }
+    }
+    let n_features: usize = columns.names.len();
+    let mut buffer: Vec<f64> = Vec::with_capacity(dataset.len() * n_features);
+    for t in 0..dataset.len() {
+        buffer.extend(columns.values.iter().map(|column| column[t]));
+    }
+    let bars = FeaturePoint::rows(buffer, dataset.len(), n_features)
+        .into_iter()
+        .enumerate()
+        .map(|(t, point)| Bar {
+            timestamp: timestamps[t],
+            mid_price: Price(columns.mid_price[t]),
+            point,
+        })
+        .collect();
+    Ok(Dataset::new(columns.names, bars))
+}
+
+struct Columns {
+    names: Vec<String>,
+    values: Vec<Vec<f64>>,
+    mid_price: Vec<f64>,
+}
+
+impl Columns {
+    fn get(&self, name: &str) -> anyhow::Result<&[f64]> {
+        if name == MID_PRICE {
+            return Ok(&self.mid_price);
+        }
+        let index =
+            self.names.iter().position(|n| n == name).ok_or_else(|| {
+                anyhow!("unknown feature source {:?}, known: {:?}", name, self.names)
+            })?;
+        Ok(&self.values[index])
+    }
+
+    fn push(&mut self, name: String, values: Vec<f64>) -> anyhow::Result<()> {
+        if self.names.contains(&name) {
+            return Err(anyhow!("feature {} is configured twice", name));
+        }
+        self.names.push(name);
+        self.values.push(values);
+        Ok(())
+    }
+}
+
+/// Mean and standard deviation over the last `window` values, NaN unless all are finite.
+/// Values are shifted by the first finite one to keep running sums well-conditioned.
+fn rolling(values: &[f64], window: usize) -> Vec<(f64, f64)> {
+    let shift: f64 = values
+        .iter()
+        .copied()
+        .find(|x| x.is_finite())
+        .unwrap_or(0.0);
+    let (mut sum, mut sum_sq, mut n_finite) = (0.0, 0.0, 0usize);
+    let mut out: Vec<(f64, f64)> = Vec::with_capacity(values.len());
+    for t in 0..values.len() {
+        let x = values[t] - shift;
+        if x.is_finite() {
+            sum += x;
+            sum_sq += x * x;
+            n_finite += 1;
+        }
+        if t >= window {
+            let old = values[t - window] - shift;
+            if old.is_finite() {
+                sum -= old;
+                sum_sq -= old * old;
+                n_finite -= 1;
+            }
+        }
+        if window == 0 || n_finite < window {
+            out.push((f64::NAN, f64::NAN));
+            continue;
+        }
+        let mean: f64 = sum / window as f64;
+        let var: f64 = (sum_sq / window as f64 - mean * mean).max(0.0);
+        out.push((mean + shift, var.sqrt()));
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dtypes::Point;
+    use crate::test_support::XPoint as P;
+
+    #[test]
+    fn test_build_features() {
+        let bars: Vec<Bar<P>> = (0..6)
+            .map(|i| Bar {
+                timestamp: i * NANOS_PER_DAY / 4,
+                mid_price: Price(100.0 * 2f64.powi(i as i32)),
+                point: P { x: i as f64 },
+            })
+            .collect();
+        let configs = [
+            FeatureConfig::Return { bars: 1 },
+            FeatureConfig::ZScore {
+                source: "x".to_string(),
+                window: 3,
+            },
+            FeatureConfig::Lag {
+                source: "return_1".to_string(),
+                bars: 2,
+            },
+            FeatureConfig::TimeOfDay,
+        ];
+        let raw = RawDataset::from_dataset(&bars.into());
+        let features = build_features(&raw, &configs).unwrap();
+        assert_eq!(
+            features.feature_names,
+            &[
+                "x",
+                "return_1",
+                "z_score_x_3",
+                "lag_return_1_2",
+                "time_of_day_sin",
+                "time_of_day_cos"
+            ]
+        );
+        let last = features[5].point.as_slice();
+        assert_eq!(last[0], 5.0);
+        assert!((last[1] - 2f64.ln()).abs() < 1e-12);
+        // the last 3 values are 3, 4, 5: std is sqrt(2/3)
+        assert!((last[2] - 1.0 / (2.0f64 / 3.0).sqrt()).abs() < 1e-9);
+        assert!((last[3] - 2f64.ln()).abs() < 1e-12);
+        // 5/4 of a day later is a quarter past midnight
+        assert!((last[4] - 1.0).abs() < 1e-12);
+        assert!(features[1].point.as_slice()[2].is_nan());
+        assert!(features[2].point.as_slice()[3].is_nan());
+        // another dataset of the same process has features of its own
+        let returns = build_features(&raw, &[FeatureConfig::Return { bars: 2 }]).unwrap();
+        assert_eq!(returns.feature_names, ["x", "return_2"]);
+        assert_eq!(returns[5].point.as_slice()[0], 5.0);
+        assert!((returns[5].point.as_slice()[1] - 4f64.ln()).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+use crate::dtypes::Point;
+
+/// Point with features configured at runtime, their names are those of its `Dataset`.
+/// Points of a dataset are views of rows of one buffer holding all their features.
+#[derive(Debug, Clone)]
+pub struct FeaturePoint {
+    buffer: Arc<[f64]>,
+    start: usize,
+    len: usize,
+}
+
+impl FeaturePoint {
+    /// Points of the `n_rows` rows of `buffer`, laid out row after row
+    pub fn rows(buffer: Vec<f64>, n_rows: usize, n_features: usize) -> Vec<Self> {
+        assert_eq!(buffer.len(), n_rows * n_features, "wrong buffer length");
+        let buffer: Arc<[f64]> = buffer.into();
+        (0..n_rows)
+            .map(|row| Self {
+                buffer: buffer.clone(),
+                start: row * n_features,
+                len: n_features,
+            })
+            .collect()
+    }
+}
+
+impl Point for FeaturePoint {
+    fn as_slice(&self) -> &[f64] {
+        &self.buffer[self.start..self.start + self.len]
+    }
+
+    fn from_slice(values: &[f64]) -> Self {
+        Self {
+            buffer: values.into(),
+            start: 0,
+            len: values.len(),
+        }
+    }
+}
@@ -0,0 +1,5 @@
+mod feature_builder;
+mod feature_point;
+
+pub use feature_builder::build_features;
+pub use feature_point::FeaturePoint;
@@ -1,16 +1,31 @@
+// lets `#[derive(Point)]` refer to `::cfr_proto::Point` inside this crate
+extern crate self as cfr_proto;
+
 mod backtest;
 mod config;
 pub mod datasets;
 mod dtypes;
+mod features;
 mod model;
 mod pyemb;
+mod run;
 pub mod strategies;
+pub mod sweep;
+#[cfg(test)]
+mod test_support;
 mod train;
 mod utils;
 
-pub use config::Config;
-pub use dtypes::{DatasetRef, Point};
-pub use model::{ModelSet, ModelSetRef, ModelType};
+pub use config::{
+    Config, GbdtConfig, IterationConfig, ModelConfig, ModelKind, OpeningPolicy, StrategyConfig,
+};
+pub use dtypes::{Dataset, DatasetRef, Point, StaticPoint};
+pub use features::FeaturePoint;
+pub use model::{ModelFile, ModelSet, ModelSetRef, ModelType};
+pub use run::{DatasetFingerprint, Manifest, RunDir};
 pub use train::Iteration;
 
-pub use backtest::{Backtester, InstrumentId, InstrumentSpec, Position, Profit, Strategy};
+pub use backtest::{
+    BacktestReport, Backtester, ExitReason, InstrumentId, InstrumentSpec, Position, Profit,
+    Strategy, Trade, TradeSide,
+};
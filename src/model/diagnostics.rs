@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+const N_CALIBRATION_BINS: usize = 10;
+
+/// Fit of predicted to realized utilities on a set of samples.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FitMetrics {
+    pub n_samples: usize,
+    pub mse: f64,
+    pub r2: f64,
+    /// Share of samples where predicted and realized utilities have the same sign
+    pub sign_accuracy: f64,
+    /// Samples sorted by prediction and split into deciles
+    pub calibration: Vec<CalibrationBin>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationBin {
+    pub n_samples: usize,
+    pub mean_predicted: f64,
+    pub mean_realized: f64,
+}
+
+/// Metrics on the samples the model was trained on and on the held out ones.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostics {
+    pub in_sample: FitMetrics,
+    pub holdout: Option<FitMetrics>,
+}
+
+impl FitMetrics {
+    pub fn new(predicted: &[f64], realized: &[f64]) -> Self {
+        let n: usize = predicted.len();
+        let n_f64: f64 = n.max(1) as f64;
+        let mean_realized: f64 = realized.iter().sum::<f64>() / n_f64;
+        let sse: f64 = predicted
+            .iter()
+            .zip(realized)
+            .map(|(p, r)| (p - r).powi(2))
+            .sum();
+        let sst: f64 = realized.iter().map(|r| (r - mean_realized).powi(2)).sum();
+        let n_same_sign: usize = predicted
+            .iter()
+            .zip(realized)
+            .filter(|(p, r)| (**p > 0.0) == (**r > 0.0))
+            .count();
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&i, &j| predicted[i].total_cmp(&predicted[j]));
+        let calibration: Vec<CalibrationBin> = (0..N_CALIBRATION_BINS)
+            .map(|bin| &order[bin * n / N_CALIBRATION_BINS..(bin + 1) * n / N_CALIBRATION_BINS])
+            .filter(|rows| !rows.is_empty())
+            .map(|rows| CalibrationBin {
+                n_samples: rows.len(),
+                mean_predicted: rows.iter().map(|&i| predicted[i]).sum::<f64>() / rows.len() as f64,
+                mean_realized: rows.iter().map(|&i| realized[i]).sum::<f64>() / rows.len() as f64,
+            })
+            .collect();
+        Self {
+            n_samples: n,
+            mse: sse / n_f64,
+            r2: if sst > 0.0 { 1.0 - sse / sst } else { f64::NAN },
+            sign_accuracy: n_same_sign as f64 / n_f64,
+            calibration,
+        }
+    }
+}
+
+impl std::fmt::Display for FitMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "n={} mse={:.6} r2={:.4} sign_accuracy={:.4}",
+            self.n_samples, self.mse, self.r2, self.sign_accuracy
+        )?;
+        let calibration: Vec<String> = self
+            .calibration
+            .iter()
+            .map(|bin| format!("{:.4}/{:.4}", bin.mean_predicted, bin.mean_realized))
+            .collect();
+        write!(f, " calibration=[{}]", calibration.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fit_metrics() {
+        let realized: Vec<f64> = (0..100).map(|i| i as f64 - 49.5).collect();
+        let perfect = FitMetrics::new(&realized, &realized);
+        assert_eq!(perfect.mse, 0.0);
+        assert_eq!(perfect.r2, 1.0);
+        assert_eq!(perfect.sign_accuracy, 1.0);
+        assert_eq!(perfect.calibration.len(), 10);
+        assert_eq!(perfect.calibration[0].n_samples, 10);
+        assert_eq!(perfect.calibration[0].mean_realized, -45.0);
+
+        let constant = FitMetrics::new(&[1.0; 100], &realized);
+        assert!(constant.r2 < 0.0);
+        assert_eq!(constant.sign_accuracy, 0.5);
+    }
+}
@@ -0,0 +1,88 @@
+use anyhow::anyhow;
+use serde_json::{json, Value};
+
+use crate::config::Combine;
+use crate::dtypes::{Point, Sample, Utility};
+use crate::model::Model;
+use crate::utils::quantile;
+
+/// Combines predictions of several trained models,
+/// e.g. of the last iterations or of runs with different seeds.
+pub struct EnsembleModel<T: Point> {
+    members: Vec<Box<dyn Model<T>>>,
+    combine: Combine,
+}
+
+impl<T: Point> EnsembleModel<T> {
+    pub fn new(members: Vec<Box<dyn Model<T>>>, combine: Combine) -> Self {
+        Self { members, combine }
+    }
+
+    fn combine(&self, values: &mut [f64]) -> f64 {
+        match self.combine {
+            Combine::Mean => values.iter().sum::<f64>() / values.len() as f64,
+            Combine::Median => quantile(values, 0.5),
+        }
+    }
+}
+
+impl<T: Point> Model<T> for EnsembleModel<T> {
+    fn infer(&self, points: &[T]) -> anyhow::Result<Vec<Utility>> {
+        let predictions: Vec<Vec<Utility>> = self
+            .members
+            .iter()
+            .map(|member| member.infer(points))
+            .collect::<anyhow::Result<_>>()?;
+        let utilities = (0..points.len())
+            .map(|i| {
+                let mut values: Vec<f64> = predictions.iter().map(|p| p[i].0).collect();
+                Utility(self.combine(&mut values))
+            })
+            .collect();
+        Ok(utilities)
+    }
+
+    fn infer_quantiles(
+        &self,
+        points: &[T],
+        quantiles: &[f64],
+    ) -> anyhow::Result<Vec<Vec<Utility>>> {
+        let predictions: Vec<Vec<Vec<Utility>>> = self
+            .members
+            .iter()
+            .map(|member| member.infer_quantiles(points, quantiles))
+            .collect::<anyhow::Result<_>>()?;
+        let utilities = (0..points.len())
+            .map(|i| {
+                (0..quantiles.len())
+                    .map(|j| {
+                        let mut values: Vec<f64> = predictions.iter().map(|p| p[i][j].0).collect();
+                        Utility(self.combine(&mut values))
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(utilities)
+    }
+
+    fn train(&self, _samples: &[Sample<T>]) -> anyhow::Result<()> {
+        Err(anyhow!("ensemble members are trained separately"))
+    }
+
+    fn params(&self) -> anyhow::Result<String> {
+        // members' params are JSON summaries, kept as text if a member's are not
+        let members: Vec<Value> = self
+            .members
+            .iter()
+            .map(|member| {
+                let params: String = member.params()?;
+                Ok(serde_json::from_str(&params).unwrap_or(Value::String(params)))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(json!({
+            "combine": self.combine,
+            "members": members,
+        })
+        .to_string())
+    }
+}
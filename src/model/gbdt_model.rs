@@ -0,0 +1,693 @@
+use std::sync::RwLock;
+
+use anyhow::anyhow;
+use ndarray::{Array2, ArrayView1};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::config::{GbdtConfig, TransformConfig};
+use crate::dtypes::{Point, Sample, Utility};
+use crate::model::pipeline::Pipeline;
+use crate::model::points::points_to_arr2;
+use crate::model::Model;
+use crate::utils::{normal_quantile, quantile};
+
+/// Gradient-boosted regression trees with histogram-based splits.
+/// The mean is trained on squared error; optional quantile ensembles
+/// are trained on pinball loss and an optional variance ensemble
+/// on squared deviations from the mean.
+pub struct GbdtModel {
+    config: GbdtConfig,
+    transforms: Vec<TransformConfig>,
+    state: RwLock<GbdtState>,
+}
+
+impl GbdtModel {
+    pub fn new(config: &GbdtConfig, transforms: &[TransformConfig]) -> Self {
+        Self {
+            config: config.clone(),
+            transforms: transforms.to_vec(),
+            state: RwLock::new(GbdtState::default()),
+        }
+    }
+}
+
+impl<T: Point> Model<T> for GbdtModel {
+    fn infer(&self, points: &[T]) -> anyhow::Result<Vec<Utility>> {
+        let state = self.state.read().unwrap();
+        let x: Array2<f64> = state.pipeline.transform(points_to_arr2(points));
+        Ok(x.rows()
+            .into_iter()
+            .map(|row| Utility(state.mean.predict(row)))
+            .collect())
+    }
+
+    fn infer_quantiles(
+        &self,
+        points: &[T],
+        quantiles: &[f64],
+    ) -> anyhow::Result<Vec<Vec<Utility>>> {
+        let state = self.state.read().unwrap();
+        let x: Array2<f64> = state.pipeline.transform(points_to_arr2(points));
+        x.rows()
+            .into_iter()
+            .map(|row| {
+                quantiles
+                    .iter()
+                    .map(|&q| state.predict_quantile(row, q).map(Utility))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn train(&self, samples: &[Sample<T>]) -> anyhow::Result<()> {
+        let points: Vec<T> = samples.iter().map(|s| s.point.clone()).collect();
+        let y: Vec<f64> = samples.iter().map(|s| s.utility.0).collect();
+        if y.is_empty() {
+            return Ok(());
+        }
+        let (pipeline, x) = Pipeline::fit(&self.transforms, points_to_arr2(&points));
+        let training_set = TrainingSet::new(&self.config, &x);
+        let mean = Ensemble::fit(&self.config, &training_set, &y, Loss::Squared);
+        let quantiles = self
+            .config
+            .quantiles
+            .iter()
+            .map(|&q| QuantileEnsemble {
+                quantile: q,
+                ensemble: Ensemble::fit(&self.config, &training_set, &y, Loss::Quantile(q)),
+            })
+            .collect();
+        let variance = if self.config.fit_variance {
+            let squared_deviations: Vec<f64> = x
+                .rows()
+                .into_iter()
+                .zip(&y)
+                .map(|(row, y)| (y - mean.predict(row)).powi(2))
+                .collect();
+            let variance = Ensemble::fit(
+                &self.config,
+                &training_set,
+                &squared_deviations,
+                Loss::Squared,
+            );
+            Some(variance)
+        } else {
+            None
+        };
+        *self.state.write().unwrap() = GbdtState {
+            pipeline,
+            mean,
+            quantiles,
+            variance,
+        };
+        Ok(())
+    }
+
+    fn save(&self) -> anyhow::Result<Value> {
+        let state = self.state.read().unwrap();
+        Ok(serde_json::to_value(&*state)?)
+    }
+
+    fn load(&self, params: &Value) -> anyhow::Result<()> {
+        *self.state.write().unwrap() = GbdtState::deserialize(params)?;
+        Ok(())
+    }
+
+    fn loss(&self) -> f64 {
+        self.state.read().unwrap().mean.loss.unwrap_or(f64::NAN)
+    }
+
+    fn params(&self) -> anyhow::Result<String> {
+        let state = self.state.read().unwrap();
+        let ensemble = &state.mean;
+        let summary = json!({
+            "base": ensemble.base,
+            "n_trees": ensemble.trees.len(),
+            "n_leaves": ensemble.trees.iter().map(|t| t.n_leaves()).sum::<usize>(),
+            "loss": ensemble.loss,
+            "quantiles": state.quantiles.iter().map(|q| q.quantile).collect::<Vec<f64>>(),
+            "variance": state.variance.is_some(),
+        });
+        Ok(summary.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct GbdtState {
+    #[serde(default, skip_serializing_if = "Pipeline::is_empty")]
+    pipeline: Pipeline,
+    #[serde(flatten)]
+    mean: Ensemble,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    quantiles: Vec<QuantileEnsemble>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    variance: Option<Ensemble>,
+}
+
+impl GbdtState {
+    /// Uses the ensemble trained for the quantile if there is one,
+    /// otherwise assumes a normal distribution around the mean.
+    fn predict_quantile(&self, x: ArrayView1<f64>, q: f64) -> anyhow::Result<f64> {
+        if let Some(qe) = self
+            .quantiles
+            .iter()
+            .find(|qe| (qe.quantile - q).abs() < 1e-9)
+        {
+            return Ok(qe.ensemble.predict(x));
+        }
+        match &self.variance {
+            Some(variance) => {
+                let std: f64 = variance.predict(x).max(0.0).sqrt();
+                Ok(self.mean.predict(x) + std * normal_quantile(q))
+            }
+            None => Err(anyhow!(
+                "quantile {} is neither trained nor derivable without a variance model",
+                q
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QuantileEnsemble {
+    quantile: f64,
+    ensemble: Ensemble,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Loss {
+    Squared,
+    /// Pinball loss for the given quantile
+    Quantile(f64),
+}
+
+impl Loss {
+    fn value(&self, residual: f64) -> f64 {
+        match *self {
+            Loss::Squared => residual * residual,
+            Loss::Quantile(q) => residual * if residual >= 0.0 { q } else { q - 1.0 },
+        }
+    }
+
+    /// Negative gradient of the loss with respect to the prediction
+    fn pseudo_residual(&self, residual: f64) -> f64 {
+        match *self {
+            Loss::Squared => residual,
+            Loss::Quantile(q) => {
+                if residual > 0.0 {
+                    q
+                } else {
+                    q - 1.0
+                }
+            }
+        }
+    }
+
+    /// Optimal constant to add to the predictions of samples with these residuals
+    fn optimal_shift(&self, residuals: &mut [f64]) -> f64 {
+        match *self {
+            Loss::Squared => residuals.iter().sum::<f64>() / residuals.len().max(1) as f64,
+            Loss::Quantile(q) => quantile(residuals, q),
+        }
+    }
+
+    fn mean(&self, indices: &[usize], y: &[f64], predictions: &[f64]) -> f64 {
+        let sum: f64 = indices
+            .iter()
+            .map(|&i| self.value(y[i] - predictions[i]))
+            .sum();
+        sum / indices.len() as f64
+    }
+}
+
+/// Train/holdout split and feature bins shared by all ensembles of a model
+struct TrainingSet<'a> {
+    x: &'a Array2<f64>,
+    train: Vec<usize>,
+    holdout: Vec<usize>,
+    binner: Binner,
+    bins: Vec<Vec<u8>>,
+}
+
+impl<'a> TrainingSet<'a> {
+    fn new(config: &GbdtConfig, x: &'a Array2<f64>) -> Self {
+        let n_samples: usize = x.nrows();
+        let mut indices: Vec<usize> = (0..n_samples).collect();
+        indices.shuffle(&mut rand::thread_rng());
+        let n_holdout: usize = if config.early_stopping_rounds > 0 {
+            (n_samples as f64 * config.holdout_fraction) as usize
+        } else {
+            0
+        };
+        let train: Vec<usize> = indices.split_off(n_holdout.min(n_samples.saturating_sub(1)));
+        let binner = Binner::fit(x, &train, config.n_bins);
+        let bins: Vec<Vec<u8>> = binner.transform(x);
+        Self {
+            x,
+            train,
+            holdout: indices,
+            binner,
+            bins,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Ensemble {
+    base: f64,
+    trees: Vec<Tree>,
+    /// Mean loss on the holdout set (training set if there is no holdout)
+    loss: Option<f64>,
+}
+
+impl Ensemble {
+    fn predict(&self, x: ArrayView1<f64>) -> f64 {
+        self.base + self.trees.iter().map(|tree| tree.predict(x)).sum::<f64>()
+    }
+
+    fn fit(config: &GbdtConfig, set: &TrainingSet, y: &[f64], loss: Loss) -> Self {
+        let n_samples: usize = y.len();
+        let (train, holdout) = (&set.train, &set.holdout);
+        let mut train_y: Vec<f64> = train.iter().map(|&i| y[i]).collect();
+        let base: f64 = loss.optimal_shift(&mut train_y);
+        let mut predictions: Vec<f64> = vec![base; n_samples];
+        let mut residuals: Vec<f64> = vec![0.0; n_samples];
+        let mut pseudo_residuals: Vec<f64> = vec![0.0; n_samples];
+        let mut trees: Vec<Tree> = Vec::with_capacity(config.n_trees);
+        let mut best_loss: f64 = f64::INFINITY;
+        let mut best_n_trees: usize = 0;
+
+        for _ in 0..config.n_trees {
+            for i in 0..n_samples {
+                residuals[i] = y[i] - predictions[i];
+                pseudo_residuals[i] = loss.pseudo_residual(residuals[i]);
+            }
+            let tree = TreeBuilder {
+                config,
+                binner: &set.binner,
+                bins: &set.bins,
+                pseudo_residuals: &pseudo_residuals,
+                residuals: &residuals,
+                loss,
+            }
+            .build(train.to_vec());
+            for (i, prediction) in predictions.iter_mut().enumerate() {
+                *prediction += tree.predict(set.x.row(i));
+            }
+            trees.push(tree);
+            if holdout.is_empty() {
+                continue;
+            }
+            let holdout_loss: f64 = loss.mean(holdout, y, &predictions);
+            if holdout_loss < best_loss {
+                best_loss = holdout_loss;
+                best_n_trees = trees.len();
+            } else if trees.len() - best_n_trees >= config.early_stopping_rounds {
+                break;
+            }
+        }
+
+        let final_loss: f64 = if holdout.is_empty() {
+            loss.mean(train, y, &predictions)
+        } else {
+            trees.truncate(best_n_trees);
+            best_loss
+        };
+        Self {
+            base,
+            trees,
+            loss: Some(final_loss),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Node {
+    /// Samples with `x[feature] <= threshold` go to `left`,
+    /// samples with a NaN feature to `left` if `missing_left`
+    Split {
+        feature: usize,
+        threshold: f64,
+        /// Defaults to the right child, where models saved without it sent NaN
+        #[serde(default)]
+        missing_left: bool,
+        left: usize,
+        right: usize,
+    },
+    Leaf {
+        value: f64,
+    },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Tree {
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    fn predict(&self, x: ArrayView1<f64>) -> f64 {
+        let mut node_index: usize = 0;
+        loop {
+            match self.nodes[node_index] {
+                Node::Split {
+                    feature,
+                    threshold,
+                    missing_left,
+                    left,
+                    right,
+                } => {
+                    let go_left = if x[feature].is_nan() {
+                        missing_left
+                    } else {
+                        x[feature] <= threshold
+                    };
+                    node_index = if go_left { left } else { right };
+                }
+                Node::Leaf { value } => return value,
+            }
+        }
+    }
+
+    fn n_leaves(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|node| matches!(node, Node::Leaf { .. }))
+            .count()
+    }
+}
+
+/// Maps feature values to quantile bins.
+/// Bin `b` holds values in `(edges[b - 1], edges[b]]`, NaN goes to `Binner::MISSING`.
+struct Binner {
+    edges: Vec<Vec<f64>>, // [n_features][n_bins - 1]
+}
+
+impl Binner {
+    const MAX_SAMPLES: usize = 100_000;
+    const MISSING: u8 = u8::MAX;
+
+    fn fit(x: &Array2<f64>, rows: &[usize], n_bins: usize) -> Self {
+        // the last `u8` is kept for missing values
+        let n_bins: usize = n_bins.clamp(2, Self::MISSING as usize);
+        let step: usize = (rows.len() / Self::MAX_SAMPLES).max(1);
+        let edges = x
+            .columns()
+            .into_iter()
+            .map(|column| {
+                let mut values: Vec<f64> = rows
+                    .iter()
+                    .step_by(step)
+                    .map(|&i| column[i])
+                    .filter(|v| v.is_finite())
+                    .collect();
+                values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let mut edges: Vec<f64> = (1..n_bins)
+                    .filter_map(|k| values.get(k * values.len() / n_bins).copied())
+                    .collect();
+                edges.dedup();
+                edges
+            })
+            .collect();
+        Self { edges }
+    }
+
+    /// Returns bin indices, `[n_features][n_samples]`.
+    fn transform(&self, x: &Array2<f64>) -> Vec<Vec<u8>> {
+        x.columns()
+            .into_iter()
+            .zip(&self.edges)
+            .map(|(column, edges)| {
+                column
+                    .iter()
+                    .map(|v| {
+                        if v.is_nan() {
+                            Self::MISSING
+                        } else {
+                            edges.partition_point(|e| e < v) as u8
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn n_bins(&self, feature: usize) -> usize {
+        self.edges[feature].len() + 1
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SplitCandidate {
+    feature: usize,
+    bin: usize,
+    missing_left: bool,
+    gain: f64,
+}
+
+struct Leaf {
+    node_index: usize,
+    depth: usize,
+    rows: Vec<usize>,
+    split: Option<SplitCandidate>,
+}
+
+/// Grows a single tree leaf-wise: the leaf with the largest gain is split first.
+/// Splits are chosen on pseudo-residuals, leaf values minimize the loss on residuals.
+struct TreeBuilder<'a> {
+    config: &'a GbdtConfig,
+    binner: &'a Binner,
+    bins: &'a [Vec<u8>],
+    pseudo_residuals: &'a [f64],
+    residuals: &'a [f64],
+    loss: Loss,
+}
+
+impl TreeBuilder<'_> {
+    fn build(&self, rows: Vec<usize>) -> Tree {
+        let mut nodes: Vec<Node> = vec![Node::Leaf { value: 0.0 }];
+        let mut leaves: Vec<Leaf> = vec![self.new_leaf(0, 0, rows)];
+        while leaves.len() < self.config.max_leaves.max(1) {
+            let best = leaves
+                .iter()
+                .enumerate()
+                .filter_map(|(i, leaf)| leaf.split.map(|split| (i, split)))
+                .max_by(|a, b| a.1.gain.partial_cmp(&b.1.gain).unwrap());
+            let (leaf_index, split) = match best {
+                None => break,
+                Some(best) => best,
+            };
+            let leaf: Leaf = leaves.swap_remove(leaf_index);
+            let feature_bins: &[u8] = &self.bins[split.feature];
+            let (left_rows, right_rows): (Vec<usize>, Vec<usize>) =
+                leaf.rows.into_iter().partition(|&i| match feature_bins[i] {
+                    Binner::MISSING => split.missing_left,
+                    bin => bin as usize <= split.bin,
+                });
+            let left: usize = nodes.len();
+            let right: usize = left + 1;
+            nodes.push(Node::Leaf { value: 0.0 });
+            nodes.push(Node::Leaf { value: 0.0 });
+            nodes[leaf.node_index] = Node::Split {
+                feature: split.feature,
+                threshold: self.binner.edges[split.feature][split.bin],
+                missing_left: split.missing_left,
+                left,
+                right,
+            };
+            leaves.push(self.new_leaf(left, leaf.depth + 1, left_rows));
+            leaves.push(self.new_leaf(right, leaf.depth + 1, right_rows));
+        }
+        for leaf in leaves {
+            let mut residuals: Vec<f64> = leaf.rows.iter().map(|&i| self.residuals[i]).collect();
+            let value: f64 = self.config.learning_rate * self.loss.optimal_shift(&mut residuals);
+            nodes[leaf.node_index] = Node::Leaf { value };
+        }
+        Tree { nodes }
+    }
+
+    fn new_leaf(&self, node_index: usize, depth: usize, rows: Vec<usize>) -> Leaf {
+        let split = if depth < self.config.max_depth {
+            self.find_split(&rows)
+        } else {
+            None
+        };
+        Leaf {
+            node_index,
+            depth,
+            rows,
+            split,
+        }
+    }
+
+    fn find_split(&self, rows: &[usize]) -> Option<SplitCandidate> {
+        let min_samples_leaf: usize = self.config.min_samples_leaf.max(1);
+        if rows.len() < 2 * min_samples_leaf {
+            return None;
+        }
+        let total_sum: f64 = rows.iter().map(|&i| self.pseudo_residuals[i]).sum();
+        let total_count: f64 = rows.len() as f64;
+        let parent_score: f64 = total_sum * total_sum / total_count;
+        let mut best: Option<SplitCandidate> = None;
+        for (feature, feature_bins) in self.bins.iter().enumerate() {
+            let n_bins: usize = self.binner.n_bins(feature);
+            let mut sums: Vec<f64> = vec![0.0; n_bins];
+            let mut counts: Vec<usize> = vec![0; n_bins];
+            let (mut missing_sum, mut missing_count): (f64, usize) = (0.0, 0);
+            for &i in rows {
+                match feature_bins[i] {
+                    Binner::MISSING => {
+                        missing_sum += self.pseudo_residuals[i];
+                        missing_count += 1;
+                    }
+                    bin => {
+                        sums[bin as usize] += self.pseudo_residuals[i];
+                        counts[bin as usize] += 1;
+                    }
+                }
+            }
+            // missing values are tried on both sides when there are any
+            let missing_sides: &[bool] = if missing_count > 0 {
+                &[false, true]
+            } else {
+                &[false]
+            };
+            let mut bin_sum: f64 = 0.0;
+            let mut bin_count: usize = 0;
+            // the last bin has no upper edge to split on
+            for bin in 0..n_bins - 1 {
+                bin_sum += sums[bin];
+                bin_count += counts[bin];
+                for &missing_left in missing_sides {
+                    let (left_sum, left_count) = if missing_left {
+                        (bin_sum + missing_sum, bin_count + missing_count)
+                    } else {
+                        (bin_sum, bin_count)
+                    };
+                    let right_count: usize = rows.len() - left_count;
+                    if left_count < min_samples_leaf || right_count < min_samples_leaf {
+                        continue;
+                    }
+                    let right_sum: f64 = total_sum - left_sum;
+                    let gain: f64 = left_sum * left_sum / left_count as f64
+                        + right_sum * right_sum / right_count as f64
+                        - parent_score;
+                    if gain > 1e-12 && best.is_none_or(|b| gain > b.gain) {
+                        best = Some(SplitCandidate {
+                            feature,
+                            bin,
+                            missing_left,
+                            gain,
+                        });
+                    }
+                }
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_support::XNoisePoint as P;
+
+    #[test]
+    fn test_fits_step_function() {
+        let samples: Vec<Sample<P>> = (0..4000)
+            .map(|i| {
+                let x = (i % 20) as f64 / 20.0;
+                let noise = ((i * 7919) % 13) as f64;
+                let utility = if x > 0.5 { 2.0 } else { -1.0 };
+                Sample {
+                    point: P { x, noise },
+                    utility: Utility(utility),
+                }
+            })
+            .collect();
+        let config = GbdtConfig {
+            min_samples_leaf: 20,
+            ..GbdtConfig::default()
+        };
+        let model = GbdtModel::new(&config, &[]);
+        Model::<P>::train(&model, &samples).unwrap();
+        let points = [P { x: 0.2, noise: 3.0 }, P { x: 0.8, noise: 3.0 }];
+        let utilities = model.infer(&points).unwrap();
+        assert!((utilities[0].0 + 1.0).abs() < 0.05);
+        assert!((utilities[1].0 - 2.0).abs() < 0.05);
+        assert!(Model::<P>::loss(&model) < 0.01);
+    }
+
+    #[test]
+    fn test_nan_goes_the_same_way_in_training_and_inference() {
+        // NaN features have their own utility, above both sides of the step
+        let samples: Vec<Sample<P>> = (0..4000)
+            .map(|i| {
+                let x = if i % 5 == 0 {
+                    f64::NAN
+                } else {
+                    (i % 20) as f64 / 20.0
+                };
+                let utility = if x.is_nan() {
+                    3.0
+                } else if x > 0.5 {
+                    2.0
+                } else {
+                    -1.0
+                };
+                Sample {
+                    point: P { x, noise: 0.0 },
+                    utility: Utility(utility),
+                }
+            })
+            .collect();
+        let config = GbdtConfig {
+            min_samples_leaf: 20,
+            ..GbdtConfig::default()
+        };
+        let model = GbdtModel::new(&config, &[]);
+        Model::<P>::train(&model, &samples).unwrap();
+        let points = [
+            P { x: 0.2, noise: 0.0 },
+            P { x: 0.8, noise: 0.0 },
+            P {
+                x: f64::NAN,
+                noise: 0.0,
+            },
+        ];
+        let utilities = model.infer(&points).unwrap();
+        assert!((utilities[0].0 + 1.0).abs() < 0.05);
+        assert!((utilities[1].0 - 2.0).abs() < 0.05);
+        assert!((utilities[2].0 - 3.0).abs() < 0.05);
+        assert!(Model::<P>::loss(&model) < 0.01);
+    }
+
+    #[test]
+    fn test_quantiles() {
+        // utility is uniform on [-1, 1] for every point
+        let samples: Vec<Sample<P>> = (0..4000)
+            .map(|i| Sample {
+                point: P {
+                    x: (i % 7) as f64,
+                    noise: 0.0,
+                },
+                utility: Utility((i % 201) as f64 / 100.0 - 1.0),
+            })
+            .collect();
+        let config = GbdtConfig {
+            min_samples_leaf: 20,
+            quantiles: vec![0.1, 0.9],
+            fit_variance: true,
+            ..GbdtConfig::default()
+        };
+        let model = GbdtModel::new(&config, &[]);
+        Model::<P>::train(&model, &samples).unwrap();
+        let points = [P { x: 3.0, noise: 0.0 }];
+        let q = model.infer_quantiles(&points, &[0.1, 0.9, 0.5]).unwrap();
+        assert!((q[0][0].0 + 0.8).abs() < 0.1);
+        assert!((q[0][1].0 - 0.8).abs() < 0.1);
+        // derived from the variance ensemble
+        assert!(q[0][2].0.abs() < 0.1);
+    }
+}
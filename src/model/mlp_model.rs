@@ -0,0 +1,487 @@
+use std::sync::RwLock;
+
+use anyhow::anyhow;
+use ndarray::{Array1, Array2, ArrayView2, Axis};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::config::{Activation, MlpConfig, TransformConfig};
+use crate::dtypes::{Point, Sample, Utility};
+use crate::model::pipeline::Pipeline;
+use crate::model::points::points_to_arr2;
+use crate::model::Model;
+
+/// Fully-connected regressor trained with Adam on squared error.
+/// Features and target are standardized with statistics stored next to the weights,
+/// NaN features are replaced by their mean.
+pub struct MlpModel {
+    config: MlpConfig,
+    transforms: Vec<TransformConfig>,
+    network: RwLock<Network>,
+}
+
+impl MlpModel {
+    pub fn new(config: &MlpConfig, transforms: &[TransformConfig]) -> Self {
+        Self {
+            config: config.clone(),
+            transforms: transforms.to_vec(),
+            network: RwLock::new(Network::default()),
+        }
+    }
+}
+
+impl<T: Point> Model<T> for MlpModel {
+    /// Predicts 0 until trained, like `GbdtModel`, e.g. for a model type without samples.
+    fn infer(&self, points: &[T]) -> anyhow::Result<Vec<Utility>> {
+        let network = self.network.read().unwrap();
+        if network.layers.is_empty() {
+            return Ok(vec![Utility(0.0); points.len()]);
+        }
+        let x: Array2<f64> = network.pipeline.transform(points_to_arr2(points));
+        Ok(network.predict(&x)?.into_iter().map(Utility).collect())
+    }
+
+    fn train(&self, samples: &[Sample<T>]) -> anyhow::Result<()> {
+        let points: Vec<T> = samples.iter().map(|s| s.point.clone()).collect();
+        let (pipeline, x) = Pipeline::fit(&self.transforms, points_to_arr2(&points));
+        let y: Vec<f64> = samples.iter().map(|s| s.utility.0).collect();
+        let mut network = Network::fit(&self.config, &x, &y)?;
+        network.pipeline = pipeline;
+        *self.network.write().unwrap() = network;
+        Ok(())
+    }
+
+    fn save(&self) -> anyhow::Result<Value> {
+        let network = self.network.read().unwrap();
+        Ok(serde_json::to_value(&*network)?)
+    }
+
+    fn load(&self, params: &Value) -> anyhow::Result<()> {
+        let network = Network::deserialize(params)?;
+        network.check_shapes()?;
+        *self.network.write().unwrap() = network;
+        Ok(())
+    }
+
+    fn loss(&self) -> f64 {
+        self.network.read().unwrap().loss.unwrap_or(f64::NAN)
+    }
+
+    fn params(&self) -> anyhow::Result<String> {
+        let network = self.network.read().unwrap();
+        let layer_sizes: Vec<usize> = network.layers.iter().map(|l| l.n_outputs).collect();
+        let summary = json!({
+            "activation": network.activation,
+            "layers": layer_sizes,
+            "loss": network.loss,
+        });
+        Ok(summary.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Layer {
+    n_inputs: usize,
+    n_outputs: usize,
+    weights: Vec<f64>, // [n_outputs][n_inputs], row-major
+    bias: Vec<f64>,
+}
+
+impl Layer {
+    fn new(n_inputs: usize, n_outputs: usize) -> Self {
+        let limit: f64 = (6.0 / (n_inputs + n_outputs) as f64).sqrt();
+        let mut rng = rand::thread_rng();
+        let weights: Vec<f64> = (0..n_inputs * n_outputs)
+            .map(|_| rng.gen_range(-limit..limit))
+            .collect();
+        Self {
+            n_inputs,
+            n_outputs,
+            weights,
+            bias: vec![0.0; n_outputs],
+        }
+    }
+
+    fn weights(&self) -> anyhow::Result<ArrayView2<'_, f64>> {
+        Ok(ArrayView2::from_shape(
+            (self.n_outputs, self.n_inputs),
+            &self.weights,
+        )?)
+    }
+
+    /// Returns pre-activations `[n_samples, n_outputs]`.
+    fn forward(&self, x: &Array2<f64>) -> anyhow::Result<Array2<f64>> {
+        Ok(x.dot(&self.weights()?.t()) + &Array1::from(self.bias.clone()))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Network {
+    #[serde(default, skip_serializing_if = "Pipeline::is_empty")]
+    pipeline: Pipeline,
+    activation: Activation,
+    feature_mean: Vec<f64>,
+    feature_std: Vec<f64>,
+    target_mean: f64,
+    target_std: f64,
+    layers: Vec<Layer>,
+    /// Mean squared error over the last training epoch
+    loss: Option<f64>,
+}
+
+impl Network {
+    /// Checks that the layers chain from the features to a single output,
+    /// e.g. for params read from a file.
+    fn check_shapes(&self) -> anyhow::Result<()> {
+        let n_features: usize = self.feature_mean.len();
+        if self.feature_std.len() != n_features {
+            return Err(anyhow!(
+                "{} feature means but {} feature standard deviations",
+                n_features,
+                self.feature_std.len()
+            ));
+        }
+        let mut n_inputs: usize = n_features;
+        for (i, layer) in self.layers.iter().enumerate() {
+            if layer.n_inputs != n_inputs
+                || layer.weights.len() != layer.n_inputs * layer.n_outputs
+                || layer.bias.len() != layer.n_outputs
+            {
+                return Err(anyhow!(
+                    "layer {} of {} inputs, {} outputs, {} weights and {} biases \
+                     does not follow {} inputs",
+                    i,
+                    layer.n_inputs,
+                    layer.n_outputs,
+                    layer.weights.len(),
+                    layer.bias.len(),
+                    n_inputs
+                ));
+            }
+            n_inputs = layer.n_outputs;
+        }
+        if !self.layers.is_empty() && n_inputs != 1 {
+            return Err(anyhow!("the output layer has {} outputs, not 1", n_inputs));
+        }
+        Ok(())
+    }
+
+    /// NaN features become 0, i.e. their mean.
+    fn standardize(&self, x: &Array2<f64>) -> Array2<f64> {
+        let mean = Array1::from(self.feature_mean.clone());
+        let std = Array1::from(self.feature_std.clone());
+        ((x - &mean) / &std).mapv(|v| if v.is_nan() { 0.0 } else { v })
+    }
+
+    /// Returns activations of every layer, the first one being the input.
+    /// The output layer is linear.
+    fn forward(&self, x: Array2<f64>) -> anyhow::Result<Vec<Array2<f64>>> {
+        let mut activations: Vec<Array2<f64>> = vec![x];
+        for (i, layer) in self.layers.iter().enumerate() {
+            let z: Array2<f64> = layer.forward(activations.last().unwrap())?;
+            let a: Array2<f64> = if i + 1 == self.layers.len() {
+                z
+            } else {
+                z.mapv(|v| self.activation.apply(v))
+            };
+            activations.push(a);
+        }
+        Ok(activations)
+    }
+
+    fn predict(&self, x: &Array2<f64>) -> anyhow::Result<Vec<f64>> {
+        let activations = self.forward(self.standardize(x))?;
+        Ok(activations
+            .last()
+            .unwrap()
+            .column(0)
+            .iter()
+            .map(|y| y * self.target_std + self.target_mean)
+            .collect())
+    }
+
+    fn fit(config: &MlpConfig, x: &Array2<f64>, y: &[f64]) -> anyhow::Result<Self> {
+        let n_samples: usize = y.len();
+        let n_features: usize = x.ncols();
+        // statistics of the finite values, NaN ones are imputed with the mean
+        let (feature_mean, feature_std): (Vec<f64>, Vec<f64>) = x
+            .columns()
+            .into_iter()
+            .map(|column| {
+                let finite: Vec<f64> = column.iter().copied().filter(|v| v.is_finite()).collect();
+                let n: f64 = finite.len().max(1) as f64;
+                let mean: f64 = finite.iter().sum::<f64>() / n;
+                let var: f64 = finite.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+                (mean, if var > 0.0 { var.sqrt() } else { 1.0 })
+            })
+            .unzip();
+        let target_mean: f64 = y.iter().sum::<f64>() / n_samples.max(1) as f64;
+        let target_var: f64 =
+            y.iter().map(|v| (v - target_mean).powi(2)).sum::<f64>() / n_samples.max(1) as f64;
+        let target_std: f64 = if target_var > 0.0 {
+            target_var.sqrt()
+        } else {
+            1.0
+        };
+
+        let mut sizes: Vec<usize> = vec![n_features];
+        sizes.extend(&config.hidden_layers);
+        sizes.push(1);
+        let layers: Vec<Layer> = sizes.windows(2).map(|w| Layer::new(w[0], w[1])).collect();
+        let mut network = Self {
+            pipeline: Pipeline::default(),
+            activation: config.activation,
+            feature_mean,
+            feature_std,
+            target_mean,
+            target_std,
+            layers,
+            loss: None,
+        };
+        if n_samples == 0 {
+            return Ok(network);
+        }
+
+        let x: Array2<f64> = network.standardize(x);
+        let y: Vec<f64> = y.iter().map(|v| (v - target_mean) / target_std).collect();
+        let mut optimizer = Adam::new(config, &network.layers);
+        let mut indices: Vec<usize> = (0..n_samples).collect();
+        let mut rng = rand::thread_rng();
+        let batch_size: usize = config.batch_size.max(1);
+        for _ in 0..config.n_epochs {
+            indices.shuffle(&mut rng);
+            let mut squared_error_sum: f64 = 0.0;
+            for batch in indices.chunks(batch_size) {
+                let xb: Array2<f64> = x.select(Axis(0), batch);
+                let yb: Array1<f64> = batch.iter().map(|&i| y[i]).collect();
+                squared_error_sum += network.step(&mut optimizer, xb, &yb)?;
+            }
+            network.loss = Some(squared_error_sum / n_samples as f64 * target_std.powi(2));
+        }
+        Ok(network)
+    }
+
+    /// Runs one minibatch update and returns the batch sum of squared errors.
+    fn step(
+        &mut self,
+        optimizer: &mut Adam,
+        x: Array2<f64>,
+        y: &Array1<f64>,
+    ) -> anyhow::Result<f64> {
+        let activations: Vec<Array2<f64>> = self.forward(x)?;
+        let output: Array1<f64> = activations.last().unwrap().column(0).to_owned();
+        let error: Array1<f64> = &output - y;
+        let batch_len: f64 = y.len() as f64;
+        let mut delta: Array2<f64> = (&error * (2.0 / batch_len)).insert_axis(Axis(1));
+        let mut gradients: Vec<(Array2<f64>, Array1<f64>)> = Vec::with_capacity(self.layers.len());
+        for (i, layer) in self.layers.iter().enumerate().rev() {
+            let input: &Array2<f64> = &activations[i];
+            let grad_weights: Array2<f64> = delta.t().dot(input);
+            let grad_bias: Array1<f64> = delta.sum_axis(Axis(0));
+            if i > 0 {
+                let derivative = input.mapv(|a| self.activation.derivative(a));
+                delta = delta.dot(&layer.weights()?) * derivative;
+            }
+            gradients.push((grad_weights, grad_bias));
+        }
+        gradients.reverse();
+        optimizer.update(&mut self.layers, &gradients);
+        Ok(error.mapv(|e| e * e).sum())
+    }
+}
+
+impl Activation {
+    fn apply(&self, z: f64) -> f64 {
+        match self {
+            Activation::Relu => z.max(0.0),
+            Activation::Tanh => z.tanh(),
+        }
+    }
+
+    /// Derivative expressed through the activation value `a = apply(z)`.
+    fn derivative(&self, a: f64) -> f64 {
+        match self {
+            Activation::Relu => {
+                if a > 0.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Activation::Tanh => 1.0 - a * a,
+        }
+    }
+}
+
+/// First and second moment estimates for one layer
+struct Moments {
+    weights_m: Vec<f64>,
+    weights_v: Vec<f64>,
+    bias_m: Vec<f64>,
+    bias_v: Vec<f64>,
+}
+
+struct Adam {
+    learning_rate: f64,
+    step: i32,
+    moments: Vec<Moments>,
+}
+
+impl Adam {
+    const BETA1: f64 = 0.9;
+    const BETA2: f64 = 0.999;
+    const EPSILON: f64 = 1e-8;
+
+    fn new(config: &MlpConfig, layers: &[Layer]) -> Self {
+        let moments = layers
+            .iter()
+            .map(|l| Moments {
+                weights_m: vec![0.0; l.weights.len()],
+                weights_v: vec![0.0; l.weights.len()],
+                bias_m: vec![0.0; l.bias.len()],
+                bias_v: vec![0.0; l.bias.len()],
+            })
+            .collect();
+        Self {
+            learning_rate: config.learning_rate,
+            step: 0,
+            moments,
+        }
+    }
+
+    fn update(&mut self, layers: &mut [Layer], gradients: &[(Array2<f64>, Array1<f64>)]) {
+        self.step += 1;
+        let correction1: f64 = 1.0 - Self::BETA1.powi(self.step);
+        let correction2: f64 = 1.0 - Self::BETA2.powi(self.step);
+        let step_size: f64 = self.learning_rate * correction2.sqrt() / correction1;
+        for ((layer, (grad_w, grad_b)), moments) in layers
+            .iter_mut()
+            .zip(gradients)
+            .zip(self.moments.iter_mut())
+        {
+            Self::update_params(
+                &mut layer.weights,
+                grad_w.iter(),
+                &mut moments.weights_m,
+                &mut moments.weights_v,
+                step_size,
+            );
+            Self::update_params(
+                &mut layer.bias,
+                grad_b.iter(),
+                &mut moments.bias_m,
+                &mut moments.bias_v,
+                step_size,
+            );
+        }
+    }
+
+    fn update_params<'a>(
+        params: &mut [f64],
+        gradient: impl Iterator<Item = &'a f64>,
+        m: &mut [f64],
+        v: &mut [f64],
+        step_size: f64,
+    ) {
+        for (((p, g), m), v) in params.iter_mut().zip(gradient).zip(m).zip(v) {
+            *m = Self::BETA1 * *m + (1.0 - Self::BETA1) * g;
+            *v = Self::BETA2 * *v + (1.0 - Self::BETA2) * g * g;
+            *p -= step_size * *m / (v.sqrt() + Self::EPSILON);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_support::XPoint as P;
+
+    #[test]
+    fn test_fits_absolute_value() {
+        let samples: Vec<Sample<P>> = (0..2000)
+            .map(|i| {
+                let x = (i % 200) as f64 / 50.0 - 2.0;
+                Sample {
+                    point: P { x },
+                    utility: Utility(x.abs() * 10.0 + 5.0),
+                }
+            })
+            .collect();
+        let config = MlpConfig {
+            hidden_layers: vec![16],
+            learning_rate: 1e-2,
+            batch_size: 32,
+            n_epochs: 50,
+            ..MlpConfig::default()
+        };
+        let model = MlpModel::new(&config, &[]);
+        Model::<P>::train(&model, &samples).unwrap();
+        let points = [P { x: -1.5 }, P { x: 0.0 }, P { x: 1.5 }];
+        let utilities = model.infer(&points).unwrap();
+        assert!((utilities[0].0 - 20.0).abs() < 2.0);
+        assert!((utilities[1].0 - 5.0).abs() < 2.0);
+        assert!((utilities[2].0 - 20.0).abs() < 2.0);
+    }
+
+    #[test]
+    fn test_nan_features_are_imputed() {
+        // every tenth point lacks its feature
+        let samples: Vec<Sample<P>> = (0..2000)
+            .map(|i| {
+                let x = (i % 200) as f64 / 50.0 - 2.0;
+                Sample {
+                    point: P {
+                        x: if i % 10 == 0 { f64::NAN } else { x },
+                    },
+                    utility: Utility(x * 10.0),
+                }
+            })
+            .collect();
+        let config = MlpConfig {
+            hidden_layers: vec![8],
+            learning_rate: 1e-2,
+            batch_size: 32,
+            n_epochs: 30,
+            ..MlpConfig::default()
+        };
+        let model = MlpModel::new(&config, &[]);
+        Model::<P>::train(&model, &samples).unwrap();
+        assert!(Model::<P>::loss(&model).is_finite());
+        let points = [P { x: 1.0 }, P { x: f64::NAN }];
+        let utilities = model.infer(&points).unwrap();
+        assert!((utilities[0].0 - 10.0).abs() < 2.0);
+        assert!(utilities[1].0.is_finite());
+    }
+
+    #[test]
+    fn test_load_checks_shapes() {
+        let model = MlpModel::new(&MlpConfig::default(), &[]);
+        let layer = |n_inputs: usize, n_outputs: usize, n_weights: usize| {
+            json!({
+                "n_inputs": n_inputs,
+                "n_outputs": n_outputs,
+                "weights": vec![0.5; n_weights],
+                "bias": vec![0.0; n_outputs],
+            })
+        };
+        let network = |layers: Vec<Value>| {
+            json!({
+                "activation": "relu",
+                "feature_mean": [0.0, 0.0],
+                "feature_std": [1.0, 1.0],
+                "target_mean": 0.0,
+                "target_std": 1.0,
+                "layers": layers,
+                "loss": null,
+            })
+        };
+        let load = |params: Value| Model::<P>::load(&model, &params);
+        assert!(load(network(vec![layer(2, 3, 6), layer(3, 1, 3)])).is_ok());
+        assert!(load(network(vec![layer(2, 3, 5), layer(3, 1, 3)])).is_err());
+        assert!(load(network(vec![layer(2, 3, 6), layer(4, 1, 4)])).is_err());
+        assert!(load(network(vec![layer(3, 1, 3)])).is_err());
+        assert!(load(network(vec![layer(2, 2, 4)])).is_err());
+    }
+}
@@ -1,14 +1,25 @@
+mod diagnostics;
+mod ensemble_model;
+mod gbdt_model;
+mod mlp_model;
+#[allow(clippy::module_inception)]
 mod model;
-mod model_id;
+mod model_file;
 mod model_set;
 mod model_type;
+mod pipeline;
+mod points;
 mod random_model;
 mod working_model;
 
 use std::sync::Arc;
 
+pub use diagnostics::{Diagnostics, FitMetrics};
+pub use ensemble_model::EnsembleModel;
+pub use gbdt_model::GbdtModel;
+pub use mlp_model::MlpModel;
 pub use model::Model;
-pub use model_id::ModelId;
+pub use model_file::{ModelFile, TrainingInfo};
 pub use model_set::ModelSet;
 pub use model_type::{ModelAction, ModelSide, ModelType};
 pub use random_model::RandomModel;
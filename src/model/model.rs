@@ -1,3 +1,6 @@
+use anyhow::anyhow;
+use serde_json::Value;
+
 use crate::dtypes::{Point, Sample, Utility};
 
 pub trait Model<T: Point>
@@ -7,25 +10,37 @@ where
     /// Returns the utility of non-trivial action.
     /// At zero position, non-trivial action is opening
     /// At non-zero position, non-trivial action is closing
-    fn infer(&self, points: &[T]) -> Vec<Utility>;
+    fn infer(&self, points: &[T]) -> anyhow::Result<Vec<Utility>>;
+
+    /// Returns quantiles of the utility distribution, `[n_points][quantiles.len()]`.
+    /// Only distributional models support it.
+    fn infer_quantiles(
+        &self,
+        _points: &[T],
+        _quantiles: &[f64],
+    ) -> anyhow::Result<Vec<Vec<Utility>>> {
+        Err(anyhow!("model does not predict utility quantiles"))
+    }
 
-    fn train(&self, _samples: &[Sample<T>]) {
+    fn train(&self, _samples: &[Sample<T>]) -> anyhow::Result<()> {
         unimplemented!()
     }
 
-    fn save(&self, _path: &str) -> anyhow::Result<()> {
-        Ok(())
+    /// Returns the trained params, stored under `model` of the `ModelFile`.
+    fn save(&self) -> anyhow::Result<Value> {
+        Ok(Value::Null)
     }
 
-    fn load(&self, _path: &str) -> anyhow::Result<()> {
+    fn load(&self, _params: &Value) -> anyhow::Result<()> {
         Ok(())
     }
 
+    /// Training loss, NaN if the model does not report it.
     fn loss(&self) -> f64 {
-        unimplemented!()
+        f64::NAN
     }
 
-    fn params(&self) -> String {
+    fn params(&self) -> anyhow::Result<String> {
         unimplemented!()
     }
 }
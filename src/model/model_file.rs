@@ -0,0 +1,241 @@
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::{ModelConfig, ModelKind};
+use crate::model::{Diagnostics, ModelType};
+
+/// Version of the model file envelope, bumped on incompatible changes.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Versioned envelope of a saved model: the model's own params under `model`
+/// together with what is needed to check that it fits the data it is applied to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelFile {
+    pub format_version: u32,
+    pub kind: ModelKind,
+    pub model_type: String,
+    pub iteration: usize,
+    pub feature_names: Vec<String>,
+    pub n_features: usize,
+    /// Hash of the dataset the training samples were collected on
+    pub dataset_hash: String,
+    pub n_samples: usize,
+    pub training_secs: f64,
+    #[serde(default)]
+    pub diagnostics: Option<Diagnostics>,
+    /// Unix time of saving
+    pub created_at: u64,
+    pub config: ModelConfig,
+    pub model: Value,
+}
+
+/// Training details recorded in the envelope.
+#[derive(Debug, Clone, Default)]
+pub struct TrainingInfo {
+    pub dataset_hash: String,
+    pub n_samples: usize,
+    pub training_secs: f64,
+    pub diagnostics: Option<Diagnostics>,
+}
+
+impl ModelFile {
+    pub fn new(
+        model_config: &ModelConfig,
+        model_type: ModelType,
+        iteration: usize,
+        feature_names: &[String],
+        training: &TrainingInfo,
+        model: Value,
+    ) -> Self {
+        let created_at: u64 = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            format_version: FORMAT_VERSION,
+            kind: model_config.kind,
+            model_type: model_type.to_string(),
+            iteration,
+            feature_names: feature_names.to_vec(),
+            n_features: feature_names.len(),
+            dataset_hash: training.dataset_hash.clone(),
+            n_samples: training.n_samples,
+            training_secs: training.training_secs,
+            diagnostics: training.diagnostics.clone(),
+            created_at,
+            config: model_config.clone(),
+            model,
+        }
+    }
+
+    pub fn write(&self, path: &str) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string(self)?)
+            .map_err(|e| anyhow!("failed to write model file {}: {}", path, e))
+    }
+
+    /// Reads the envelope and checks it against the model kind and the dataset features,
+    /// warning if the model was trained on another dataset than that of `dataset_hash`.
+    /// Files saved before the envelope was introduced are returned as bare params
+    /// if they have the shape of the params of `kind`.
+    pub fn read(
+        path: &str,
+        kind: ModelKind,
+        feature_names: &[String],
+        dataset_hash: Option<&str>,
+    ) -> anyhow::Result<Value> {
+        let text: String = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read model file {}: {}", path, e))?;
+        let value: Value = serde_json::from_str(&text)?;
+        if value.get("format_version").is_none() {
+            check_legacy(&value, kind, feature_names)
+                .map_err(|e| e.context(format!("incompatible model file {}", path)))?;
+            return Ok(value);
+        }
+        let file: ModelFile = serde_json::from_value(value)
+            .map_err(|e| anyhow!("malformed model file {}: {}", path, e))?;
+        file.check(kind, feature_names)
+            .map_err(|e| e.context(format!("incompatible model file {}", path)))?;
+        match dataset_hash {
+            Some(hash) if hash != file.dataset_hash => eprintln!(
+                "warning: model file {} was trained on dataset {}, not on the configured training dataset {}",
+                path, file.dataset_hash, hash
+            ),
+            _ => {}
+        }
+        Ok(file.model)
+    }
+
+    fn check(&self, kind: ModelKind, feature_names: &[String]) -> anyhow::Result<()> {
+        if self.format_version > FORMAT_VERSION {
+            return Err(anyhow!(
+                "format version {} is newer than supported {}",
+                self.format_version,
+                FORMAT_VERSION
+            ));
+        }
+        if self.kind != kind {
+            return Err(anyhow!(
+                "model kind {:?} does not match configured {:?}",
+                self.kind,
+                kind
+            ));
+        }
+        if self.n_features != feature_names.len() || self.feature_names != feature_names {
+            return Err(anyhow!(
+                "features {:?} do not match dataset features {:?}",
+                self.feature_names,
+                feature_names
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Checks the bare params of a `kind` model saved before the envelope was introduced,
+/// including their number of features where the params tell it.
+fn check_legacy(params: &Value, kind: ModelKind, feature_names: &[String]) -> anyhow::Result<()> {
+    let (keys, n_features): (&[&str], Option<usize>) = match kind {
+        ModelKind::Python => (&["coef", "intercept"], array_len(&params["coef"])),
+        ModelKind::Gbdt => (&["base", "trees"], None),
+        ModelKind::Mlp => (
+            &["layers", "feature_mean"],
+            array_len(&params["feature_mean"]),
+        ),
+    };
+    if let Some(key) = keys.iter().find(|key| params.get(key).is_none()) {
+        return Err(anyhow!(
+            "neither a versioned model file nor the params of a {:?} model, which have {:?}",
+            kind,
+            key
+        ));
+    }
+    // transforms may change the number of features the params see
+    match n_features {
+        Some(n) if params.get("pipeline").is_none() && n != feature_names.len() => Err(anyhow!(
+            "params of {} features do not match {} dataset features {:?}",
+            n,
+            feature_names.len(),
+            feature_names
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Length of an array unless empty, as in the params of untrained models.
+fn array_len(value: &Value) -> Option<usize> {
+    value.as_array().map(Vec::len).filter(|&n| n > 0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use serde_json::json;
+
+    #[test]
+    fn test_check_legacy() {
+        let features: &[String] = &["a".to_string(), "b".to_string()];
+        let python = json!({"coef": [0.5, 1.0], "intercept": 0.1, "loss": null});
+        assert!(check_legacy(&python, ModelKind::Python, features).is_ok());
+        assert!(check_legacy(&python, ModelKind::Mlp, features).is_err());
+        let python = json!({"coef": [0.5, 1.0, 2.0], "intercept": 0.1});
+        assert!(check_legacy(&python, ModelKind::Python, features).is_err());
+        let untrained = json!({"coef": [], "intercept": 0});
+        assert!(check_legacy(&untrained, ModelKind::Python, features).is_ok());
+        assert!(check_legacy(&json!({"weights": [1.0]}), ModelKind::Gbdt, features).is_err());
+        assert!(check_legacy(&json!([1.0, 2.0]), ModelKind::Python, features).is_err());
+    }
+
+    #[test]
+    fn test_read_rejects_malformed_files() {
+        let dir = temp_dir("model_file_test");
+        let path: String = dir
+            .join("1_opening_long.json")
+            .to_str()
+            .unwrap()
+            .to_string();
+        let features: Vec<String> = vec!["a".to_string(), "b".to_string()];
+        let model_type = ModelType::try_from(0).unwrap();
+        let model_config = ModelConfig {
+            kind: ModelKind::Gbdt,
+            ..ModelConfig::default()
+        };
+        let training = TrainingInfo {
+            dataset_hash: "0123".to_string(),
+            ..TrainingInfo::default()
+        };
+        let file = ModelFile::new(
+            &model_config,
+            model_type,
+            1,
+            &features,
+            &training,
+            json!({}),
+        );
+        let valid: Value = serde_json::to_value(&file).unwrap();
+        let read = |value: &Value| -> anyhow::Result<Value> {
+            std::fs::write(&path, value.to_string()).unwrap();
+            ModelFile::read(&path, ModelKind::Gbdt, &features, Some("0123"))
+        };
+        assert!(read(&valid).is_ok());
+        // another dataset only warns
+        assert!(ModelFile::read(&path, ModelKind::Gbdt, &features, Some("4567")).is_ok());
+        assert!(ModelFile::read(&path, ModelKind::Mlp, &features, None).is_err());
+        assert!(ModelFile::read(&path, ModelKind::Gbdt, &features[..1], None).is_err());
+
+        let mut newer = valid.clone();
+        newer["format_version"] = json!(FORMAT_VERSION + 1);
+        assert!(read(&newer).is_err());
+        let mut truncated = valid.clone();
+        truncated.as_object_mut().unwrap().remove("feature_names");
+        let error = read(&truncated).unwrap_err().to_string();
+        assert!(error.contains("malformed"), "{}", error);
+        let mut mistyped = valid.clone();
+        mistyped["n_features"] = json!("two");
+        assert!(read(&mistyped).is_err());
+        std::fs::write(&path, "{\"format_version\": 1,").unwrap();
+        assert!(ModelFile::read(&path, ModelKind::Gbdt, &features, None).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,67 +1,303 @@
 use std::sync::Arc;
 
+use crate::config::{EnsembleConfig, ModelConfig, ModelKind};
 use crate::dtypes::Point;
-use crate::model::{Model, ModelAction, ModelType, RandomModel, WorkingModel};
+use crate::model::{
+    EnsembleModel, GbdtModel, MlpModel, Model, ModelAction, ModelFile, ModelType, RandomModel,
+    TrainingInfo, WorkingModel,
+};
 
 pub struct ModelSet<T: Point> {
     iteration_index: usize,
     output_dir: String,
+    model_config: ModelConfig,
+    /// Features of the dataset, checked against those of the loaded model files
+    feature_names: Vec<String>,
+    /// Hash of the training dataset, compared with that of the loaded model files
+    dataset_hash: Option<String>,
     models: Arc<Vec<Box<dyn Model<T>>>>,
 }
 
 impl<T: Point> ModelSet<T> {
-    pub fn new(iteration_index: usize, output_dir: &str) -> Arc<Self> {
+    pub fn new(
+        iteration_index: usize,
+        output_dir: &str,
+        model_config: &ModelConfig,
+        feature_names: &[String],
+        dataset_hash: Option<&str>,
+    ) -> anyhow::Result<Arc<Self>> {
         let models: Vec<Box<dyn Model<T>>> = ModelType::all()
             .into_iter()
-            .map(|_model_type| {
-                let model: Box<dyn Model<T>> = if iteration_index == 0 {
-                    Box::new(RandomModel {})
-                } else {
-                    Box::new(WorkingModel::new())
+            .map(|_model_type| Self::new_model(iteration_index, model_config))
+            .collect::<anyhow::Result<_>>()?;
+        let object = Self {
+            iteration_index,
+            output_dir: output_dir.to_string(),
+            model_config: model_config.clone(),
+            feature_names: feature_names.to_vec(),
+            dataset_hash: dataset_hash.map(str::to_string),
+            models: Arc::new(models),
+        };
+        Ok(Arc::new(object))
+    }
+
+    /// Creates a set of `EnsembleModel`s combining the models saved for the last
+    /// `n_iterations` iterations up to `iteration_index` in each of `models_dirs`.
+    /// Closing models are taken from the previous iterations, as in
+    /// `load_model_params_with_close_from_previous_iteration`, but never from iteration 0,
+    /// whose models are random.
+    pub fn new_ensemble(
+        iteration_index: usize,
+        models_dirs: &[String],
+        model_config: &ModelConfig,
+        ensemble_config: &EnsembleConfig,
+        feature_names: &[String],
+        dataset_hash: Option<&str>,
+    ) -> anyhow::Result<Arc<Self>> {
+        let first_iteration: usize = iteration_index
+            .saturating_sub(ensemble_config.n_iterations.max(1) - 1)
+            .max(1);
+        let models: Vec<Box<dyn Model<T>>> = ModelType::all()
+            .into_iter()
+            .map(|model_type| {
+                let iterations = match model_type.action {
+                    ModelAction::Opening => first_iteration..=iteration_index,
+                    ModelAction::Closing => {
+                        (first_iteration - 1).max(1)..=iteration_index.saturating_sub(1).max(1)
+                    }
                 };
-                model
+                let mut members: Vec<Box<dyn Model<T>>> = Vec::new();
+                for dir in models_dirs.iter() {
+                    for iteration in iterations.clone() {
+                        let member: Box<dyn Model<T>> = Self::new_model(iteration, model_config)?;
+                        Self::load_model(
+                            member.as_ref(),
+                            dir,
+                            iteration,
+                            model_type,
+                            model_config,
+                            feature_names,
+                            dataset_hash,
+                        )?;
+                        members.push(member);
+                    }
+                }
+                let model: Box<dyn Model<T>> =
+                    Box::new(EnsembleModel::new(members, ensemble_config.combine));
+                Ok(model)
             })
-            .collect();
+            .collect::<anyhow::Result<_>>()?;
         let object = Self {
             iteration_index,
-            output_dir: output_dir.to_string(),
+            output_dir: models_dirs.first().cloned().unwrap_or_default(),
+            model_config: model_config.clone(),
+            feature_names: feature_names.to_vec(),
+            dataset_hash: dataset_hash.map(str::to_string),
             models: Arc::new(models),
         };
-        Arc::new(object)
+        Ok(Arc::new(object))
+    }
+
+    fn new_model(
+        iteration_index: usize,
+        model_config: &ModelConfig,
+    ) -> anyhow::Result<Box<dyn Model<T>>> {
+        let model: Box<dyn Model<T>> = if iteration_index == 0 {
+            Box::new(RandomModel {})
+        } else {
+            match model_config.kind {
+                ModelKind::Python => Box::new(WorkingModel::new(model_config)?),
+                ModelKind::Gbdt => {
+                    Box::new(GbdtModel::new(&model_config.gbdt, &model_config.transforms))
+                }
+                ModelKind::Mlp => {
+                    Box::new(MlpModel::new(&model_config.mlp, &model_config.transforms))
+                }
+            }
+        };
+        Ok(model)
+    }
+
+    fn model_path(dir: &str, iteration_index: usize, model_type: ModelType) -> String {
+        format!("{}/{}_{}.json", dir, iteration_index, model_type)
+    }
+
+    /// Iteration 0 plays with random models, which are not saved.
+    fn load_model(
+        model: &dyn Model<T>,
+        dir: &str,
+        iteration_index: usize,
+        model_type: ModelType,
+        model_config: &ModelConfig,
+        feature_names: &[String],
+        dataset_hash: Option<&str>,
+    ) -> anyhow::Result<()> {
+        if iteration_index == 0 {
+            return Ok(());
+        }
+        let path: String = Self::model_path(dir, iteration_index, model_type);
+        let params = ModelFile::read(&path, model_config.kind, feature_names, dataset_hash)?;
+        model
+            .load(&params)
+            .map_err(|e| e.context(format!("failed to load model {}", path)))
     }
 
-    pub fn model(&self, model_type: ModelType) -> &Box<dyn Model<T>> {
+    pub fn model(&self, model_type: ModelType) -> &dyn Model<T> {
         let model_index: usize = model_type.into();
-        &self.models[model_index]
+        self.models[model_index].as_ref()
     }
 
     pub fn len(&self) -> usize {
         self.models.len()
     }
 
-    pub fn load_model_params(&self) {
-        ModelType::all().into_iter().for_each(|model_type| {
-            let model_path = format!(
-                "{}/{}_{}.json",
-                self.output_dir, self.iteration_index, model_type
-            );
-            let model_index: usize = model_type.into();
-            self.models[model_index].load(&model_path).unwrap();
-        });
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
     }
 
-    pub fn load_model_params_with_close_from_previous_iteration(&self) {
-        ModelType::all().into_iter().for_each(|model_type| {
+    pub fn load_model_params(&self) -> anyhow::Result<()> {
+        for model_type in ModelType::all() {
+            Self::load_model(
+                self.model(model_type),
+                &self.output_dir,
+                self.iteration_index,
+                model_type,
+                &self.model_config,
+                &self.feature_names,
+                self.dataset_hash.as_deref(),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn load_model_params_with_close_from_previous_iteration(&self) -> anyhow::Result<()> {
+        for model_type in ModelType::all() {
             let iteration_index = match model_type.action {
                 ModelAction::Opening => self.iteration_index,
                 ModelAction::Closing => self.iteration_index - 1,
             };
-            let model_path = format!(
-                "{}/{}_{}.json",
-                self.output_dir, iteration_index, model_type
-            );
+            Self::load_model(
+                self.model(model_type),
+                &self.output_dir,
+                iteration_index,
+                model_type,
+                &self.model_config,
+                &self.feature_names,
+                self.dataset_hash.as_deref(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Saves each model in a `ModelFile` envelope, `training` is indexed by model type.
+    pub fn save_model_params(&self, training: &[TrainingInfo]) -> anyhow::Result<()> {
+        std::fs::create_dir_all(&self.output_dir)?;
+        for model_type in ModelType::all() {
             let model_index: usize = model_type.into();
-            self.models[model_index].load(&model_path).unwrap();
-        });
+            let path: String = Self::model_path(&self.output_dir, self.iteration_index, model_type);
+            let file = ModelFile::new(
+                &self.model_config,
+                model_type,
+                self.iteration_index,
+                &self.feature_names,
+                &training[model_index],
+                self.model(model_type).save()?,
+            );
+            file.write(&path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dtypes::{Sample, Utility};
+    use crate::model::ModelSide;
+    use crate::test_support::{temp_dir, XPoint as P};
+
+    #[test]
+    fn test_untrained_models_predict_zero() {
+        let dir = temp_dir("model_set_test");
+        let dir: &str = dir.to_str().unwrap();
+        let features: &[String] = &["x".to_string()];
+        let samples: Vec<Sample<P>> = (0..200)
+            .map(|i| Sample {
+                point: P { x: i as f64 },
+                utility: Utility(1.0 + i as f64 / 100.0),
+            })
+            .collect();
+        let trained = ModelType {
+            side: ModelSide::Long,
+            action: ModelAction::Opening,
+        };
+        for kind in [ModelKind::Gbdt, ModelKind::Mlp] {
+            let model_config = ModelConfig {
+                kind,
+                ..ModelConfig::default()
+            };
+            // only one model type gets samples
+            let model_set: Arc<ModelSet<P>> =
+                ModelSet::new(1, dir, &model_config, features, None).unwrap();
+            model_set.model(trained).train(&samples).unwrap();
+            let training = vec![TrainingInfo::default(); ModelType::N_VARIANTS];
+            model_set.save_model_params(&training).unwrap();
+
+            let model_set: Arc<ModelSet<P>> =
+                ModelSet::new(1, dir, &model_config, features, None).unwrap();
+            model_set.load_model_params().unwrap();
+            for model_type in ModelType::all() {
+                let utility = model_set.model(model_type).infer(&[P { x: 50.0 }]).unwrap()[0];
+                assert_eq!(utility.0 == 0.0, model_type != trained, "{:?}", kind);
+            }
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_ensemble_spanning_iteration_1() {
+        let dir = temp_dir("ensemble_test");
+        let dir: &str = dir.to_str().unwrap();
+        let features: &[String] = &["x".to_string()];
+        let model_config = ModelConfig {
+            kind: ModelKind::Gbdt,
+            ..ModelConfig::default()
+        };
+        // every model of iteration i predicts 2 * i
+        for iteration in 1..=2 {
+            let samples: Vec<Sample<P>> = (0..200)
+                .map(|i| Sample {
+                    point: P { x: i as f64 },
+                    utility: Utility(2.0 * iteration as f64),
+                })
+                .collect();
+            let model_set: Arc<ModelSet<P>> =
+                ModelSet::new(iteration, dir, &model_config, features, None).unwrap();
+            for model_type in ModelType::all() {
+                model_set.model(model_type).train(&samples).unwrap();
+            }
+            let training = vec![TrainingInfo::default(); ModelType::N_VARIANTS];
+            model_set.save_model_params(&training).unwrap();
+        }
+        let ensemble_config = EnsembleConfig {
+            n_iterations: 2,
+            runs: vec![],
+            models_dirs: vec![],
+            combine: Default::default(),
+        };
+        let dirs: &[String] = &[dir.to_string()];
+        let model_set: Arc<ModelSet<P>> =
+            ModelSet::new_ensemble(2, dirs, &model_config, &ensemble_config, features, None)
+                .unwrap();
+        for model_type in ModelType::all() {
+            let utility = model_set.model(model_type).infer(&[P { x: 50.0 }]).unwrap()[0];
+            // closing models of iteration 1 only, opening ones of iterations 1 and 2
+            let expected: f64 = match model_type.action {
+                ModelAction::Opening => 3.0,
+                ModelAction::Closing => 2.0,
+            };
+            assert!((utility.0 - expected).abs() < 1e-9, "{}", model_type);
+        }
+        std::fs::remove_dir_all(dir).unwrap();
     }
 }
@@ -14,17 +14,17 @@ impl TryFrom<usize> for ModelType {
         if value >= Self::N_VARIANTS {
             Err(anyhow!("ModelType index out of bounds"))
         } else {
-            let side = unsafe { std::mem::transmute((value / 2) as u32) };
-            let action = unsafe { std::mem::transmute((value % 2) as u32) };
+            let side = unsafe { std::mem::transmute::<u32, ModelSide>((value / 2) as u32) };
+            let action = unsafe { std::mem::transmute::<u32, ModelAction>((value % 2) as u32) };
             Ok(Self { side, action })
         }
     }
 }
 
-impl Into<usize> for ModelType {
-    fn into(self) -> usize {
-        let side: usize = self.side as usize;
-        let action: usize = self.action as usize;
+impl From<ModelType> for usize {
+    fn from(model_type: ModelType) -> usize {
+        let side: usize = model_type.side as usize;
+        let action: usize = model_type.action as usize;
         side * 2 + action
     }
 }
@@ -0,0 +1,188 @@
+use ndarray::{concatenate, Array1, Array2, Axis};
+use serde::{Deserialize, Serialize};
+
+use crate::config::TransformConfig;
+use crate::utils::quantile;
+
+/// Feature preprocessing fitted on training samples and stored with the model,
+/// so that inference applies exactly the transforms seen in training.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Pipeline {
+    steps: Vec<Transform>,
+}
+
+impl Pipeline {
+    /// Fits the steps one after another, each on the output of the previous one.
+    /// Returns the pipeline and the transformed features.
+    pub fn fit(configs: &[TransformConfig], x: Array2<f64>) -> (Self, Array2<f64>) {
+        let mut x = x;
+        let mut steps: Vec<Transform> = Vec::with_capacity(configs.len());
+        for config in configs {
+            let step = Transform::fit(config, &x);
+            x = step.apply(x);
+            steps.push(step);
+        }
+        (Self { steps }, x)
+    }
+
+    pub fn transform(&self, x: Array2<f64>) -> Array2<f64> {
+        self.steps.iter().fold(x, |x, step| step.apply(x))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Transform {
+    ZScore { mean: Vec<f64>, std: Vec<f64> },
+    Clip { min: f64, max: f64 },
+    Winsorize { lower: Vec<f64>, upper: Vec<f64> },
+    Log,
+    Polynomial { degree: u32 },
+    Interactions,
+}
+
+impl Transform {
+    fn fit(config: &TransformConfig, x: &Array2<f64>) -> Self {
+        match *config {
+            TransformConfig::ZScore => {
+                let (mean, std) = x
+                    .columns()
+                    .into_iter()
+                    .map(|column| {
+                        let values: Vec<f64> = finite_values(column.iter());
+                        let n: f64 = values.len().max(1) as f64;
+                        let mean: f64 = values.iter().sum::<f64>() / n;
+                        let var: f64 = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+                        let std: f64 = if var > 0.0 { var.sqrt() } else { 1.0 };
+                        (mean, std)
+                    })
+                    .unzip();
+                Transform::ZScore { mean, std }
+            }
+            TransformConfig::Clip { min, max } => Transform::Clip { min, max },
+            TransformConfig::Winsorize {
+                lower_quantile,
+                upper_quantile,
+            } => {
+                let (lower, upper) = x
+                    .columns()
+                    .into_iter()
+                    .map(|column| {
+                        let mut values: Vec<f64> = finite_values(column.iter());
+                        // columns without finite values, e.g. still warming up, are left
+                        // as they are rather than clamped to NaN quantiles
+                        if values.is_empty() {
+                            return (f64::MIN, f64::MAX);
+                        }
+                        let lower: f64 = quantile(&mut values, lower_quantile);
+                        let upper: f64 = quantile(&mut values, upper_quantile);
+                        (lower, upper)
+                    })
+                    .unzip();
+                Transform::Winsorize { lower, upper }
+            }
+            TransformConfig::Log => Transform::Log,
+            TransformConfig::Polynomial { degree } => Transform::Polynomial { degree },
+            TransformConfig::Interactions => Transform::Interactions,
+        }
+    }
+
+    fn apply(&self, mut x: Array2<f64>) -> Array2<f64> {
+        match self {
+            Transform::ZScore { mean, std } => {
+                x -= &Array1::from(mean.clone());
+                x /= &Array1::from(std.clone());
+                x
+            }
+            Transform::Clip { min, max } => {
+                x.mapv_inplace(|v| v.clamp(*min, *max));
+                x
+            }
+            Transform::Winsorize { lower, upper } => {
+                for (mut column, (lower, upper)) in
+                    x.columns_mut().into_iter().zip(lower.iter().zip(upper))
+                {
+                    column.mapv_inplace(|v| v.clamp(*lower, *upper));
+                }
+                x
+            }
+            // signed log keeps the transform defined for negative features
+            Transform::Log => {
+                x.mapv_inplace(|v| v.signum() * v.abs().ln_1p());
+                x
+            }
+            Transform::Polynomial { degree } => {
+                let powers: Vec<Array2<f64>> = (2..=*degree as i32)
+                    .map(|power| x.mapv(|v| v.powi(power)))
+                    .collect();
+                let mut views = vec![x.view()];
+                views.extend(powers.iter().map(|p| p.view()));
+                concatenate(Axis(1), &views).unwrap()
+            }
+            Transform::Interactions => {
+                let n_features: usize = x.ncols();
+                let products: Vec<Array2<f64>> = (0..n_features)
+                    .flat_map(|i| (i + 1..n_features).map(move |j| (i, j)))
+                    .map(|(i, j)| (&x.column(i) * &x.column(j)).insert_axis(Axis(1)))
+                    .collect();
+                let mut views = vec![x.view()];
+                views.extend(products.iter().map(|p| p.view()));
+                concatenate(Axis(1), &views).unwrap()
+            }
+        }
+    }
+}
+
+fn finite_values<'a>(values: impl Iterator<Item = &'a f64>) -> Vec<f64> {
+    values.copied().filter(|v| v.is_finite()).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn test_fit_and_transform_agree() {
+        let configs = [
+            TransformConfig::Winsorize {
+                lower_quantile: 0.0,
+                upper_quantile: 0.5,
+            },
+            TransformConfig::ZScore,
+            TransformConfig::Interactions,
+        ];
+        let x = array![[1.0, 10.0], [2.0, 20.0], [3.0, 30.0], [100.0, 40.0]];
+        let (pipeline, fitted) = Pipeline::fit(&configs, x.clone());
+        assert_eq!(fitted.shape(), &[4, 3]);
+        assert_eq!(pipeline.transform(x), fitted);
+        // values above the median, 2.5, including the outlier, are clamped to it
+        assert_eq!(fitted[[2, 0]], fitted[[3, 0]]);
+
+        let json = serde_json::to_string(&pipeline).unwrap();
+        let restored: Pipeline = serde_json::from_str(&json).unwrap();
+        let y = array![[2.5, 25.0]];
+        assert_eq!(restored.transform(y.clone()), pipeline.transform(y));
+    }
+
+    #[test]
+    fn test_winsorize_without_finite_values() {
+        let configs = [TransformConfig::Winsorize {
+            lower_quantile: 0.1,
+            upper_quantile: 0.9,
+        }];
+        let x = array![[f64::NAN, 1.0], [f64::NAN, 2.0], [f64::NAN, 3.0]];
+        let (pipeline, fitted) = Pipeline::fit(&configs, x);
+        assert!(fitted.column(0).iter().all(|v| v.is_nan()));
+
+        let json = serde_json::to_string(&pipeline).unwrap();
+        let restored: Pipeline = serde_json::from_str(&json).unwrap();
+        let y = restored.transform(array![[5.0, 5.0]]);
+        assert_eq!(y[[0, 0]], 5.0);
+        assert!(y[[0, 1]] < 3.0);
+    }
+}
@@ -0,0 +1,13 @@
+use ndarray::Array2;
+
+use crate::dtypes::Point;
+
+/// Packs points into a `[n_points, n_features]` array.
+pub fn points_to_arr2<T: Point>(points: &[T]) -> Array2<f64> {
+    let values: Vec<f64> = points
+        .iter()
+        .flat_map(|point| point.as_slice().iter().copied())
+        .collect();
+    let n_features: usize = points.first().map_or(0, |point| point.as_slice().len());
+    Array2::from_shape_vec((points.len(), n_features), values).unwrap()
+}
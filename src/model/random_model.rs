@@ -5,8 +5,8 @@ use crate::model::Model;
 pub struct RandomModel {}
 
 impl<T: Point> Model<T> for RandomModel {
-    fn infer(&self, points: &[T]) -> Vec<Utility> {
-        points
+    fn infer(&self, points: &[T]) -> anyhow::Result<Vec<Utility>> {
+        let utilities = points
             .iter()
             .map(|_| {
                 if rand::random() {
@@ -15,6 +15,7 @@ impl<T: Point> Model<T> for RandomModel {
                     Utility(-1.0)
                 }
             })
-            .collect()
+            .collect();
+        Ok(utilities)
     }
 }
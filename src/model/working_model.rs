@@ -1,44 +1,46 @@
+use anyhow::anyhow;
 use ndarray::Array2;
-use serde_json::Value;
-use std::pin::Pin;
+use serde_json::{json, Value};
+use std::sync::RwLock;
 
+use crate::config::{ModelConfig, TransformConfig};
 use crate::dtypes::Utility;
 use crate::dtypes::{Point, Sample};
+use crate::model::pipeline::Pipeline;
+use crate::model::points::points_to_arr2;
 use crate::model::Model;
 use crate::pyemb;
 
+#[derive(Default)]
 struct Params {
     coef: Vec<f64>,
     intercept: f64,
-    _pin: std::marker::PhantomPinned,
-}
-
-impl Params {
-    fn new() -> Pin<Box<Self>> {
-        let object = Self {
-            coef: vec![],
-            intercept: 0.0,
-            _pin: std::marker::PhantomPinned,
-        };
-        Box::pin(object)
-    }
+    /// Inference is done in Rust with `coef` and `intercept` only for the identity-link
+    /// linear models the plugin marks as `linear`
+    linear: bool,
 }
 
 pub struct WorkingModel {
     model_id: u64,
-    params: Pin<Box<Params>>,
+    params: RwLock<Params>,
+    transforms: Vec<TransformConfig>,
+    /// Stored under the `pipeline` key of the params JSON
+    pipeline: RwLock<Pipeline>,
 }
 
 impl WorkingModel {
-    pub fn new() -> Self {
-        Self {
-            model_id: pyemb::new_model(),
-            params: Params::new(),
-        }
-    }
-
-    fn params_mut(&self) -> &mut Params {
-        unsafe { &mut *(std::ptr::addr_of!(*self.params) as *mut Params) }
+    pub fn new(config: &ModelConfig) -> anyhow::Result<Self> {
+        let spec: String = json!({
+            "estimator": config.estimator,
+            "params": config.params,
+        })
+        .to_string();
+        Ok(Self {
+            model_id: pyemb::new_model(&spec)?,
+            params: RwLock::new(Params::default()),
+            transforms: config.transforms.clone(),
+            pipeline: RwLock::new(Pipeline::default()),
+        })
     }
 }
 
@@ -49,75 +51,85 @@ impl Drop for WorkingModel {
 }
 
 impl<T: Point> Model<T> for WorkingModel {
-    fn infer(&self, points: &[T]) -> Vec<Utility> {
-        let x: Array2<f64> = points_to_arr2(points);
-        // let p: Array2<f64> = pyemb::predict(self.model_id, &x);
-        // p.column(0).iter().map(|&x| Utility(x)).collect()
+    fn infer(&self, points: &[T]) -> anyhow::Result<Vec<Utility>> {
+        let x: Array2<f64> = self
+            .pipeline
+            .read()
+            .unwrap()
+            .transform(points_to_arr2(points));
+        let params = self.params.read().unwrap();
+        if !params.linear {
+            let p: Array2<f64> = pyemb::predict(self.model_id, &x)?;
+            return Ok(p.column(0).iter().map(|&x| Utility(x)).collect());
+        }
         let p2 = x
             .rows()
             .into_iter()
             .map(|v| {
-                let u = v
-                    .iter()
-                    .zip(&self.params.coef)
-                    .map(|(x, c)| x * c)
-                    .sum::<f64>()
-                    + self.params.intercept;
+                let u =
+                    v.iter().zip(&params.coef).map(|(x, c)| x * c).sum::<f64>() + params.intercept;
                 Utility(u)
             })
             .collect();
-        p2
+        Ok(p2)
     }
 
-    fn train(&self, samples: &[Sample<T>]) {
+    fn train(&self, samples: &[Sample<T>]) -> anyhow::Result<()> {
         let points: Vec<T> = samples.iter().map(|s| s.point.clone()).collect();
-        let x: Array2<f64> = points_to_arr2(&points);
+        let (pipeline, x) = Pipeline::fit(&self.transforms, points_to_arr2(&points));
         let y: Vec<f64> = samples.iter().map(|s| s.utility.0).collect();
-        let y = Array2::from_shape_vec((y.len(), 1), y).unwrap();
-        pyemb::fit(self.model_id, &x, &y);
+        let y = Array2::from_shape_vec((y.len(), 1), y)?;
+        pyemb::fit(self.model_id, &x, &y)?;
+        *self.pipeline.write().unwrap() = pipeline;
+        Ok(())
     }
 
-    fn save(&self, path: &str) -> anyhow::Result<()> {
-        let params = pyemb::get_params(self.model_id);
-        std::fs::write(path, params).map_err(|e| e.into())
+    fn save(&self) -> anyhow::Result<Value> {
+        let mut params: Value = serde_json::from_str(&pyemb::get_params(self.model_id)?)?;
+        let pipeline = self.pipeline.read().unwrap();
+        if !pipeline.is_empty() {
+            params["pipeline"] = serde_json::to_value(&*pipeline)?;
+        }
+        Ok(params)
     }
 
-    fn load(&self, path: &str) -> anyhow::Result<()> {
-        if !std::path::Path::new(&path).exists() {
-            panic!("model file not found: {}", path);
-        }
-        let params = std::fs::read_to_string(path)?;
-        pyemb::set_params(self.model_id, &params);
-        let params: Value = serde_json::from_str(&params)?;
-        self.params_mut().coef = params["coef"]
+    fn load(&self, params: &Value) -> anyhow::Result<()> {
+        pyemb::set_params(self.model_id, &params.to_string())?;
+        let coef: Vec<f64> = params["coef"]
             .as_array()
-            .unwrap()
+            .ok_or_else(|| anyhow!("model params lack coef"))?
             .iter()
-            .map(|x| x.as_f64().unwrap())
-            .collect();
-        self.params_mut().intercept = params["intercept"].as_f64().unwrap();
+            .map(|x| x.as_f64().ok_or_else(|| anyhow!("non-numeric coef: {}", x)))
+            .collect::<anyhow::Result<_>>()?;
+        let intercept: f64 = params["intercept"]
+            .as_f64()
+            .ok_or_else(|| anyhow!("model params lack intercept"))?;
+        // files saved before `linear` was recorded restore only the coefficients
+        let linear = params["linear"]
+            .as_bool()
+            .unwrap_or_else(|| params.get("state").is_none() && !coef.is_empty());
+        *self.params.write().unwrap() = Params {
+            linear,
+            coef,
+            intercept,
+        };
+        *self.pipeline.write().unwrap() = match params.get("pipeline") {
+            Some(pipeline) => serde_json::from_value(pipeline.clone())?,
+            None => Pipeline::default(),
+        };
         Ok(())
     }
 
+    /// NaN if the plugin fails or reports no loss; `params` surfaces plugin errors.
     fn loss(&self) -> f64 {
-        let params = pyemb::get_params(self.model_id);
-        let v: Value = serde_json::from_str(&params).unwrap();
-        v.as_object().unwrap()["loss"].as_f64().unwrap_or(f64::NAN)
-    }
-
-    fn params(&self) -> String {
         pyemb::get_params(self.model_id)
+            .ok()
+            .and_then(|params| serde_json::from_str::<Value>(&params).ok())
+            .and_then(|params| params["loss"].as_f64())
+            .unwrap_or(f64::NAN)
     }
-}
 
-fn points_to_arr2<T: Point>(points: &[T]) -> Array2<f64> {
-    let point_size_in_f64: usize = std::mem::size_of::<T>() / std::mem::size_of::<f64>();
-    let arr_size_in_f64: usize = points.len() * point_size_in_f64;
-    let arr_f64: Vec<f64> = unsafe {
-        std::ptr::slice_from_raw_parts(points.as_ptr() as *const u8 as *const f64, arr_size_in_f64)
-            .as_ref()
+    fn params(&self) -> anyhow::Result<String> {
+        pyemb::get_params(self.model_id)
     }
-    .unwrap()
-    .to_vec();
-    Array2::from_shape_vec((points.len(), point_size_in_f64), arr_f64).unwrap()
 }
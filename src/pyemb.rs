@@ -1,7 +1,7 @@
 #![allow(dead_code)]
+use anyhow::anyhow;
 use ndarray::Array2;
-use std::ffi;
-use std::ffi::CString;
+use std::ffi::{c_char, CString};
 
 #[repr(C)]
 struct Array2Ptr {
@@ -30,55 +30,95 @@ mod _pyemb {
     #[link(name = "pyemb", kind = "dylib")]
     #[allow(dead_code)]
     extern "C" {
-        pub(super) fn new_model() -> u64;
+        pub(super) fn new_model(spec: *const c_char, model_id: *mut u64) -> i32;
         pub(super) fn delete_model(model_id: u64);
-        pub(super) fn fit(model_id: u64, x: Array2Ptr, y: Array2Ptr);
-        pub(super) fn predict(output: Array2Ptr, model_id: u64, x: Array2Ptr);
-        pub(super) fn get_params(model_id: u64, output: *const c_char);
-        pub(super) fn set_params(model_id: u64, params: *const c_char);
+        pub(super) fn fit(model_id: u64, x: Array2Ptr, y: Array2Ptr) -> i32;
+        pub(super) fn predict(output: Array2Ptr, model_id: u64, x: Array2Ptr) -> i32;
+        pub(super) fn get_params(
+            model_id: u64,
+            output: *mut c_char,
+            capacity: u64,
+            length: *mut u64,
+        ) -> i32;
+        pub(super) fn set_params(model_id: u64, params: *const c_char) -> i32;
+        pub(super) fn get_last_error(output: *mut c_char, capacity: i32) -> i32;
     }
 }
 
-pub fn new_model() -> u64 {
-    unsafe { _pyemb::new_model() }
+/// Converts a status code returned by the plugin into a result,
+/// attaching the Python traceback on failure.
+fn check_status(status: i32, call: &str) -> anyhow::Result<()> {
+    if status == 0 {
+        Ok(())
+    } else {
+        Err(anyhow!("pyemb::{} failed:\n{}", call, last_error()))
+    }
+}
+
+/// Returns the traceback of the last exception raised inside the plugin on this thread.
+pub fn last_error() -> String {
+    let len: i32 = unsafe { _pyemb::get_last_error(std::ptr::null_mut(), 0) };
+    let mut output: Vec<u8> = vec![0; len as usize + 1];
+    unsafe { _pyemb::get_last_error(output.as_mut_ptr() as *mut c_char, output.len() as i32) };
+    output.truncate(len as usize);
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+/// Instantiates the estimator described by a JSON spec and returns its id.
+pub fn new_model(spec: &str) -> anyhow::Result<u64> {
+    let spec = CString::new(spec)?;
+    let mut model_id: u64 = 0;
+    let status = unsafe { _pyemb::new_model(spec.as_ptr(), &mut model_id) };
+    check_status(status, "new_model")?;
+    Ok(model_id)
 }
 
 pub fn delete_model(model_id: u64) {
     unsafe { _pyemb::delete_model(model_id) }
 }
 
-pub fn fit(model_id: u64, x: &Array2<f64>, y: &Array2<f64>) {
+pub fn fit(model_id: u64, x: &Array2<f64>, y: &Array2<f64>) -> anyhow::Result<()> {
     let x_ptr = Array2Ptr::new(x);
     let y_ptr = Array2Ptr::new(y);
-    unsafe { _pyemb::fit(model_id, x_ptr, y_ptr) }
+    let status = unsafe { _pyemb::fit(model_id, x_ptr, y_ptr) };
+    check_status(status, "fit")
 }
 
-pub fn predict(model_id: u64, x: &Array2<f64>) -> Array2<f64> {
+pub fn predict(model_id: u64, x: &Array2<f64>) -> anyhow::Result<Array2<f64>> {
     let x_ptr = Array2Ptr::new(x);
     let n_samples = x.shape()[0];
     let mut output = Array2::zeros((n_samples, 1));
     output.fill(f64::NAN);
     let output_ptr = Array2Ptr::new(&output);
-    unsafe { _pyemb::predict(output_ptr, model_id, x_ptr) }
-    output
+    let status = unsafe { _pyemb::predict(output_ptr, model_id, x_ptr) };
+    check_status(status, "predict")?;
+    Ok(output)
 }
 
-pub fn get_params(model_id: u64) -> String {
-    let mut output: Vec<u8> = Vec::with_capacity(1024);
-    for _ in 0..1024 {
-        output.push(0);
+/// Fetches serialized params in two phases: the first call queries
+/// the length, the second one fills a buffer of that size.
+pub fn get_params(model_id: u64) -> anyhow::Result<String> {
+    let mut length: u64 = 0;
+    let status = unsafe { _pyemb::get_params(model_id, std::ptr::null_mut(), 0, &mut length) };
+    check_status(status, "get_params")?;
+    let mut output: Vec<u8> = vec![0; length as usize + 1];
+    let capacity = output.len() as u64;
+    let output_ptr = output.as_mut_ptr() as *mut c_char;
+    let status = unsafe { _pyemb::get_params(model_id, output_ptr, capacity, &mut length) };
+    check_status(status, "get_params")?;
+    if length >= capacity {
+        return Err(anyhow!(
+            "pyemb::get_params: params grew from {} to {} bytes between calls",
+            capacity - 1,
+            length
+        ));
     }
-    let output = unsafe { CString::from_vec_unchecked(output) };
-    let c_str = output.as_ptr();
-    unsafe { _pyemb::get_params(model_id, c_str) };
-    let output = unsafe { ffi::CStr::from_ptr(output.as_ptr() as *const _) }
-        .to_str()
-        .unwrap()
-        .to_string();
-    output
+    output.truncate(length as usize);
+    Ok(String::from_utf8(output)?)
 }
 
-pub fn set_params(model_id: u64, params: &str) {
-    let params = std::ffi::CString::new(params).unwrap();
-    unsafe { _pyemb::set_params(model_id, params.as_ptr()) }
+pub fn set_params(model_id: u64, params: &str) -> anyhow::Result<()> {
+    let params = CString::new(params)?;
+    let status = unsafe { _pyemb::set_params(model_id, params.as_ptr()) };
+    check_status(status, "set_params")
 }
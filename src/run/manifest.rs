@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+use crate::dtypes::{Dataset, Point};
+use crate::model::Diagnostics;
+use crate::utils::dataset_hash;
+
+/// What a training run was made of and how each iteration went,
+/// stored as `manifest.json` in the run directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub run_id: String,
+    /// Unix time of creation
+    pub created_at: u64,
+    /// Commit of the source tree, if run inside a git checkout
+    pub git_revision: Option<String>,
+    /// Whether the source tree had uncommitted changes
+    pub git_dirty: bool,
+    pub dataset: DatasetFingerprint,
+    /// In order of training, continued runs appending to it
+    pub iterations: Vec<IterationMetrics>,
+}
+
+/// Identifies the bars, after resampling and derived features, the models were trained on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DatasetFingerprint {
+    pub path: String,
+    pub hash: String,
+    pub n_bars: usize,
+    pub feature_names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IterationMetrics {
+    pub iteration: usize,
+    pub n_plays: usize,
+    pub mean_play_length: f64,
+    pub mean_prediction: f64,
+    pub models: Vec<ModelMetrics>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelMetrics {
+    pub model_type: String,
+    pub n_samples: usize,
+    pub training_secs: f64,
+    pub diagnostics: Option<Diagnostics>,
+}
+
+impl DatasetFingerprint {
+    pub fn new<T: Point>(path: &str, dataset: &Dataset<T>) -> Self {
+        Self {
+            path: path.to_string(),
+            hash: dataset_hash(dataset),
+            n_bars: dataset.len(),
+            feature_names: dataset.feature_names.clone(),
+        }
+    }
+}
@@ -0,0 +1,6 @@
+mod manifest;
+mod run_dir;
+
+pub use manifest::{DatasetFingerprint, IterationMetrics, Manifest, ModelMetrics};
+pub(crate) use run_dir::format_run_id;
+pub use run_dir::RunDir;
@@ -0,0 +1,263 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+
+use crate::backtest::BacktestReport;
+use crate::config::{Config, EnsembleConfig};
+use crate::run::{DatasetFingerprint, IterationMetrics, Manifest};
+
+const MANIFEST_FILE: &str = "manifest.json";
+/// Run id resolving to the most recently created run
+const LATEST: &str = "latest";
+
+/// Directory of a training run under `runs_dir`, named by its UTC creation time,
+/// holding the resolved config, the manifest and the models of all iterations.
+pub struct RunDir {
+    path: PathBuf,
+    manifest: Manifest,
+}
+
+impl RunDir {
+    /// Creates a new run directory and writes the config and the initial manifest.
+    pub fn create(
+        runs_dir: &str,
+        config: &Config,
+        dataset: DatasetFingerprint,
+    ) -> anyhow::Result<Self> {
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?;
+        let base_id: String = format_run_id(now.as_secs() as i64);
+        let mut run_id: String = base_id.clone();
+        let mut suffix: usize = 1;
+        while Path::new(runs_dir).join(&run_id).exists() {
+            suffix += 1;
+            run_id = format!("{}-{}", base_id, suffix);
+        }
+        let path: PathBuf = Path::new(runs_dir).join(&run_id);
+        std::fs::create_dir_all(path.join("models"))
+            .map_err(|e| anyhow!("failed to create run directory {}: {}", path.display(), e))?;
+        let (git_revision, git_dirty) = git_revision();
+        let run = Self {
+            path,
+            manifest: Manifest {
+                run_id,
+                created_at: now.as_secs(),
+                git_revision,
+                git_dirty,
+                dataset,
+                iterations: vec![],
+            },
+        };
+        run.write_config(config, "config.toml")?;
+        run.write_manifest()?;
+        Ok(run)
+    }
+
+    /// Opens an existing run to continue training it on the same dataset.
+    pub fn open(
+        runs_dir: &str,
+        run_id: &str,
+        dataset: &DatasetFingerprint,
+    ) -> anyhow::Result<Self> {
+        let path: PathBuf = Self::resolve(runs_dir, run_id)?;
+        let manifest_path: PathBuf = path.join(MANIFEST_FILE);
+        let text: String = std::fs::read_to_string(&manifest_path)
+            .map_err(|e| anyhow!("failed to read {}: {}", manifest_path.display(), e))?;
+        let manifest: Manifest = serde_json::from_str(&text)
+            .map_err(|e| anyhow!("malformed manifest {}: {}", manifest_path.display(), e))?;
+        if manifest.dataset.hash != dataset.hash {
+            return Err(anyhow!(
+                "run {} was trained on dataset {} ({}), not {} ({})",
+                manifest.run_id,
+                manifest.dataset.path,
+                manifest.dataset.hash,
+                dataset.path,
+                dataset.hash
+            ));
+        }
+        Ok(Self { path, manifest })
+    }
+
+    /// Path of the run with the given id, or of the latest run for `latest`.
+    pub fn resolve(runs_dir: &str, run_id: &str) -> anyhow::Result<PathBuf> {
+        if run_id != LATEST {
+            let path: PathBuf = Path::new(runs_dir).join(run_id);
+            if !path.join(MANIFEST_FILE).exists() {
+                return Err(anyhow!("no run {} in {}", run_id, runs_dir));
+            }
+            return Ok(path);
+        }
+        let mut run_ids: Vec<String> = std::fs::read_dir(runs_dir)
+            .map_err(|e| anyhow!("failed to read runs directory {}: {}", runs_dir, e))?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().join(MANIFEST_FILE).exists())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        // ids sort by creation time, with same-second suffixes after the first
+        run_ids.sort_by_key(|id| (id[..id.len().min(15)].to_string(), id.len(), id.clone()));
+        let run_id: &String = run_ids
+            .last()
+            .ok_or_else(|| anyhow!("no runs in {}", runs_dir))?;
+        Ok(Path::new(runs_dir).join(run_id))
+    }
+
+    /// Models directory of the run with the given id, or of the latest run for `latest`.
+    pub fn models_dir_of(runs_dir: &str, run_id: &str) -> anyhow::Result<String> {
+        Ok(Self::resolve(runs_dir, run_id)?
+            .join("models")
+            .to_string_lossy()
+            .to_string())
+    }
+
+    /// Models directory of the backtest: `backtest.models_dir` if set, else that of `backtest.run`.
+    pub fn backtest_models_dir(config: &Config) -> anyhow::Result<String> {
+        match &config.backtest.models_dir {
+            Some(models_dir) => Ok(models_dir.clone()),
+            None => Self::models_dir_of(&config.runs_dir, &config.backtest.run),
+        }
+    }
+
+    /// Models directories of the ensemble's runs and its `models_dirs`,
+    /// or the backtest's own if it lists neither.
+    pub fn ensemble_models_dirs(
+        config: &Config,
+        ensemble: &EnsembleConfig,
+    ) -> anyhow::Result<Vec<String>> {
+        let mut models_dirs: Vec<String> = ensemble
+            .runs
+            .iter()
+            .map(|run_id| Self::models_dir_of(&config.runs_dir, run_id))
+            .collect::<anyhow::Result<_>>()?;
+        models_dirs.extend(ensemble.models_dirs.iter().cloned());
+        if models_dirs.is_empty() {
+            models_dirs.push(Self::backtest_models_dir(config)?);
+        }
+        Ok(models_dirs)
+    }
+
+    /// Writes the report of a backtest of a run as `backtest_{iteration}.json` in the run,
+    /// returning its path, or `None` for models outside of the runs.
+    pub fn write_backtest_report(
+        config: &Config,
+        report: &BacktestReport,
+    ) -> anyhow::Result<Option<PathBuf>> {
+        if config.backtest.models_dir.is_some() {
+            return Ok(None);
+        }
+        let path: PathBuf = Self::resolve(&config.runs_dir, &config.backtest.run)?
+            .join(format!("backtest_{}.json", config.backtest.iteration));
+        std::fs::write(&path, serde_json::to_string_pretty(report)?)
+            .map_err(|e| anyhow!("failed to write {}: {}", path.display(), e))?;
+        Ok(Some(path))
+    }
+
+    pub fn read_backtest_report(
+        runs_dir: &str,
+        run_id: &str,
+        iteration: usize,
+    ) -> anyhow::Result<BacktestReport> {
+        let path: PathBuf =
+            Self::resolve(runs_dir, run_id)?.join(format!("backtest_{}.json", iteration));
+        let text: String = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow!("failed to read {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    pub fn id(&self) -> &str {
+        &self.manifest.run_id
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn models_dir(&self) -> String {
+        self.path.join("models").to_string_lossy().to_string()
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    /// Writes the resolved config under `name`, e.g. once per continuation of the run.
+    pub fn write_config(&self, config: &Config, name: &str) -> anyhow::Result<()> {
+        let path: PathBuf = self.path.join(name);
+        std::fs::write(&path, toml::to_string(config)?)
+            .map_err(|e| anyhow!("failed to write {}: {}", path.display(), e))
+    }
+
+    /// Adds or replaces the metrics of an iteration and rewrites the manifest.
+    pub fn record_iteration(&mut self, metrics: IterationMetrics) -> anyhow::Result<()> {
+        self.manifest
+            .iterations
+            .retain(|m| m.iteration != metrics.iteration);
+        self.manifest.iterations.push(metrics);
+        self.write_manifest()
+    }
+
+    fn write_manifest(&self) -> anyhow::Result<()> {
+        let path: PathBuf = self.path.join(MANIFEST_FILE);
+        std::fs::write(&path, serde_json::to_string_pretty(&self.manifest)?)
+            .map_err(|e| anyhow!("failed to write {}: {}", path.display(), e))
+    }
+}
+
+/// Commit and dirtiness of the working directory's git checkout, if any.
+fn git_revision() -> (Option<String>, bool) {
+    let git = |args: &[&str]| -> Option<String> {
+        let output = std::process::Command::new("git").args(args).output().ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    let revision: Option<String> = git(&["rev-parse", "HEAD"]);
+    let dirty: bool = revision.is_some()
+        && git(&["status", "--porcelain"]).is_some_and(|status| !status.is_empty());
+    (revision, dirty)
+}
+
+/// `YYYYMMDD-HHMMSS` in UTC.
+pub(crate) fn format_run_id(unix_secs: i64) -> String {
+    let (days, secs) = (unix_secs.div_euclid(86_400), unix_secs.rem_euclid(86_400));
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    )
+}
+
+/// Proleptic Gregorian date of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_format_run_id() {
+        assert_eq!(format_run_id(0), "19700101-000000");
+        assert_eq!(format_run_id(1_709_209_800), "20240229-123000");
+        assert_eq!(format_run_id(1_792_409_045), "20261019-112405");
+    }
+}
@@ -0,0 +1,268 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use anyhow::anyhow;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::backtest::{Order, Position, Signal, Strategy, Trade, TradeSide};
+use crate::config::BaselineConfig;
+use crate::dtypes::Bar;
+use crate::{DatasetRef, Point};
+
+/// Creates the baseline strategy trading the first of `positions` over `dataset`,
+/// matching random entries to the models' `trades` unless configured otherwise.
+pub fn new_baseline<T: Point>(
+    config: &BaselineConfig,
+    positions: Rc<RefCell<Vec<Position>>>,
+    dataset: &DatasetRef<T>,
+    trades: &[Trade],
+) -> anyhow::Result<Box<dyn Strategy<T>>> {
+    let strategy: Box<dyn Strategy<T>> = match config {
+        BaselineConfig::BuyAndHold => Box::new(BuyAndHold {
+            positions,
+            last_timestamp: dataset.last().map_or(i64::MAX, |bar| bar.timestamp),
+        }),
+        BaselineConfig::Random {
+            seed,
+            entry_probability,
+            holding_bars,
+        } => {
+            let n_trades: usize = trades.len().max(1);
+            let bars_held: usize = trades.iter().map(|trade| trade.bars_held).sum();
+            let holding_bars: usize =
+                holding_bars.unwrap_or((bars_held as f64 / n_trades as f64).round() as usize);
+            // entries happen only at bars without a position
+            let flat_bars: usize = dataset.len().saturating_sub(bars_held).max(1);
+            let entry_probability: f64 =
+                entry_probability.unwrap_or((n_trades as f64 / flat_bars as f64).min(1.0));
+            Box::new(RandomEntry {
+                positions,
+                rng: StdRng::seed_from_u64(*seed),
+                entry_probability,
+                holding: HoldingPeriod::new(holding_bars),
+            })
+        }
+        BaselineConfig::Threshold {
+            feature,
+            threshold,
+            below,
+            side,
+            holding_bars,
+        } => {
+            let feature_index: usize = dataset
+                .feature_names
+                .iter()
+                .position(|name| name == feature)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "baseline {}: unknown feature {:?}, known: {:?}",
+                        config.name(),
+                        feature,
+                        dataset.feature_names
+                    )
+                })?;
+            Box::new(Threshold {
+                positions,
+                feature_index,
+                threshold: *threshold,
+                below: *below,
+                side: *side,
+                holding: HoldingPeriod::new(*holding_bars),
+            })
+        }
+    };
+    Ok(strategy)
+}
+
+fn order(position: &Position, size: i32) -> Order {
+    Order {
+        instrument_id: position.instrument_id().clone(),
+        size,
+        signal: Signal::default(),
+    }
+}
+
+/// Long one contract from the first bar, closed at the last one.
+pub struct BuyAndHold {
+    positions: Rc<RefCell<Vec<Position>>>,
+    last_timestamp: i64,
+}
+
+impl<T: Point> Strategy<T> for BuyAndHold {
+    fn trade_decision(&mut self, bar: &Bar<T>) -> anyhow::Result<Option<Order>> {
+        let position = &self.positions.borrow()[0];
+        let size: i32 = if bar.timestamp >= self.last_timestamp {
+            -position.position()
+        } else if position.position() == 0 {
+            1
+        } else {
+            0
+        };
+        Ok((size != 0).then(|| order(position, size)))
+    }
+}
+
+/// Counts the bars a position is held for, to close it after a fixed number of them.
+struct HoldingPeriod {
+    holding_bars: usize,
+    bars_held: usize,
+}
+
+impl HoldingPeriod {
+    fn new(holding_bars: usize) -> Self {
+        Self {
+            holding_bars: holding_bars.max(1),
+            bars_held: 0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.bars_held = 0;
+    }
+
+    /// The closing order of an open position once it is held long enough.
+    fn close(&mut self, position: &Position) -> Option<Order> {
+        self.bars_held += 1;
+        (self.bars_held >= self.holding_bars).then(|| order(position, -position.position()))
+    }
+}
+
+/// Enters long or short with equal odds at random bars and holds for a fixed period.
+pub struct RandomEntry {
+    positions: Rc<RefCell<Vec<Position>>>,
+    rng: StdRng,
+    entry_probability: f64,
+    holding: HoldingPeriod,
+}
+
+impl<T: Point> Strategy<T> for RandomEntry {
+    fn trade_decision(&mut self, _bar: &Bar<T>) -> anyhow::Result<Option<Order>> {
+        let position = &self.positions.borrow()[0];
+        if position.position() != 0 {
+            return Ok(self.holding.close(position));
+        }
+        self.holding.reset();
+        if !self.rng.gen_bool(self.entry_probability) {
+            return Ok(None);
+        }
+        let size: i32 = if self.rng.gen_bool(0.5) { 1 } else { -1 };
+        Ok(Some(order(position, size)))
+    }
+}
+
+/// Opens a side when a single feature is beyond a threshold and holds for a fixed period.
+pub struct Threshold {
+    positions: Rc<RefCell<Vec<Position>>>,
+    feature_index: usize,
+    threshold: f64,
+    below: bool,
+    side: TradeSide,
+    holding: HoldingPeriod,
+}
+
+impl<T: Point> Strategy<T> for Threshold {
+    fn trade_decision(&mut self, bar: &Bar<T>) -> anyhow::Result<Option<Order>> {
+        let position = &self.positions.borrow()[0];
+        if position.position() != 0 {
+            return Ok(self.holding.close(position));
+        }
+        self.holding.reset();
+        let value: f64 = bar.point.as_slice()[self.feature_index];
+        let is_signal: bool = if self.below {
+            value < self.threshold
+        } else {
+            value > self.threshold
+        };
+        let size: i32 = match self.side {
+            TradeSide::Long => 1,
+            TradeSide::Short => -1,
+        };
+        Ok(is_signal.then(|| order(position, size)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backtest::{Backtester, InstrumentId, InstrumentSpec};
+    use crate::dtypes::Price;
+    use crate::test_support::XPoint as P;
+    use std::sync::Arc;
+
+    fn backtest(config: &BaselineConfig) -> Vec<Trade> {
+        let dataset: DatasetRef<P> = Arc::new(
+            [
+                (100.0, 0.0),
+                (101.0, 1.0),
+                (103.0, 0.0),
+                (102.0, 0.0),
+                (104.0, 1.0),
+            ]
+            .iter()
+            .enumerate()
+            .map(|(i, &(price, signal))| Bar {
+                timestamp: i as i64,
+                mid_price: Price(price),
+                point: P { x: signal },
+            })
+            .collect(),
+        );
+        let instrument_spec = InstrumentSpec {
+            multiplier: 1.0,
+            fee: 0.0,
+            roll_fee: 0.0,
+        };
+        let instrument_id = InstrumentId {
+            index: 0,
+            symbol: "NQ".to_string(),
+        };
+        let positions = Rc::new(RefCell::new(vec![Position::new(
+            instrument_id,
+            instrument_spec,
+        )]));
+        let strategy = new_baseline(config, positions.clone(), &dataset, &[]).unwrap();
+        let mut backtester = Backtester::new(positions, dataset, strategy);
+        backtester.run().unwrap();
+        backtester.trades()
+    }
+
+    #[test]
+    fn test_baselines() {
+        let trades = backtest(&BaselineConfig::BuyAndHold);
+        assert_eq!(trades.len(), 1);
+        assert_eq!(
+            (trades[0].entry_price, trades[0].exit_price),
+            (100.0, 104.0)
+        );
+
+        let trades = backtest(&BaselineConfig::Threshold {
+            feature: "x".to_string(),
+            threshold: 0.5,
+            below: false,
+            side: TradeSide::Short,
+            holding_bars: 2,
+        });
+        assert_eq!(trades.len(), 1);
+        assert_eq!((trades[0].side, trades[0].bars_held), (TradeSide::Short, 2));
+        assert_eq!(trades[0].profit, 101.0 - 102.0);
+    }
+
+    #[test]
+    fn test_random_entry() {
+        let random = |seed: u64, entry_probability: f64| {
+            backtest(&BaselineConfig::Random {
+                seed,
+                entry_probability: Some(entry_probability),
+                holding_bars: Some(2),
+            })
+        };
+        assert!(random(1, 0.0).is_empty());
+        // entries at every flat bar, held for the holding period; the one of bar 3 stays open
+        let trades = random(1, 1.0);
+        assert_eq!(trades.len(), 1);
+        assert_eq!((trades[0].entry_timestamp, trades[0].bars_held), (0, 2));
+        let sides = |trades: &[Trade]| trades.iter().map(|t| t.side).collect::<Vec<_>>();
+        assert_eq!(sides(&trades), sides(&random(1, 1.0)));
+    }
+}
@@ -1,26 +1,53 @@
-use crate::backtest::{Order, Position, Strategy};
+use crate::backtest::{Order, Position, Signal, Strategy};
+use crate::config::StrategyConfig;
 use crate::dtypes::Bar;
 use crate::model::{Model, ModelAction, ModelSide, ModelType};
 use crate::{ModelSetRef, Point};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use std::cell::RefCell;
 use std::rc::Rc;
 
 pub struct BasicStrategy<T: Point> {
     positions: Rc<RefCell<Vec<Position>>>,
     model_set: ModelSetRef<T>,
+    config: StrategyConfig,
+    /// Samples the side to open for the sampling opening policies
+    rng: StdRng,
 }
 
 impl<T: Point> BasicStrategy<T> {
-    pub fn new(positions: Rc<RefCell<Vec<Position>>>, model_set: ModelSetRef<T>) -> Self {
+    pub fn new(
+        positions: Rc<RefCell<Vec<Position>>>,
+        model_set: ModelSetRef<T>,
+        config: &StrategyConfig,
+    ) -> Self {
         Self {
             positions,
             model_set,
+            config: config.clone(),
+            rng: StdRng::seed_from_u64(config.seed),
+        }
+    }
+
+    /// Returns the utility of opening, or a non-positive value
+    /// if the configured lower quantile of the utility is not positive.
+    fn opening_utility(&self, model_type: ModelType, bar: &Bar<T>) -> anyhow::Result<f64> {
+        let model: &dyn Model<T> = self.model_set.model(model_type);
+        let point = std::slice::from_ref(&bar.point);
+        let utility: f64 = model.infer(point)?[0].0;
+        match self.config.open_quantile {
+            Some(q) if utility > 0.0 => {
+                let lower_utility: f64 = model.infer_quantiles(point, &[q])?[0][0].0;
+                Ok(if lower_utility > 0.0 { utility } else { 0.0 })
+            }
+            _ => Ok(utility),
         }
     }
 }
 
 impl<T: Point> Strategy<T> for BasicStrategy<T> {
-    fn trade_decision(&mut self, bar: &Bar<T>) -> Option<Order> {
+    fn trade_decision(&mut self, bar: &Bar<T>) -> anyhow::Result<Option<Order>> {
         let position: i32 = self.positions.borrow()[0].position();
         let instrument_id = self.positions.borrow()[0].instrument_id().clone();
         if position == 0 {
@@ -28,27 +55,31 @@ impl<T: Point> Strategy<T> for BasicStrategy<T> {
                 side: ModelSide::Long,
                 action: ModelAction::Opening,
             };
-            let open_long_model: &Box<dyn Model<T>> = self.model_set.model(open_long_model_type);
-            let open_long_utility: f64 = open_long_model.infer(&[bar.point.clone()])[0].0;
+            let open_long_utility: f64 = self.opening_utility(open_long_model_type, bar)?;
             let open_short_model_type = ModelType {
                 side: ModelSide::Short,
                 action: ModelAction::Opening,
             };
-            let open_short_model: &Box<dyn Model<T>> = self.model_set.model(open_short_model_type);
-            let open_short_utility: f64 = open_short_model.infer(&[bar.point.clone()])[0].0;
-            if open_long_utility > 0.0 && open_long_utility >= open_short_utility {
-                Some(Order {
-                    instrument_id: instrument_id.clone(),
-                    size: 1,
-                })
-            } else if open_short_utility > 0.0 {
-                Some(Order {
-                    instrument_id: instrument_id.clone(),
-                    size: -1,
-                })
-            } else {
-                None
-            }
+            let open_short_utility: f64 = self.opening_utility(open_short_model_type, bar)?;
+            let signal = Signal {
+                open_long_utility: Some(open_long_utility),
+                open_short_utility: Some(open_short_utility),
+                hold_utility: None,
+            };
+            let side: Option<ModelSide> = self.config.opening_policy.decide(
+                open_long_utility,
+                open_short_utility,
+                &mut self.rng,
+            );
+            let order = side.map(|side| Order {
+                instrument_id: instrument_id.clone(),
+                size: match side {
+                    ModelSide::Long => 1,
+                    ModelSide::Short => -1,
+                },
+                signal,
+            });
+            Ok(order)
         } else {
             let close_model_type = ModelType {
                 side: if position > 0 {
@@ -58,16 +89,22 @@ impl<T: Point> Strategy<T> for BasicStrategy<T> {
                 },
                 action: ModelAction::Closing,
             };
-            let close_model: &Box<dyn Model<T>> = self.model_set.model(close_model_type);
-            let utility_of_doing_nothing: f64 = close_model.infer(&[bar.point.clone()])[0].0;
-            if utility_of_doing_nothing > 0.0 {
+            let close_model: &dyn Model<T> = self.model_set.model(close_model_type);
+            let utility_of_doing_nothing: f64 =
+                close_model.infer(std::slice::from_ref(&bar.point))?[0].0;
+            let order = if utility_of_doing_nothing > 0.0 {
                 None
             } else {
                 Some(Order {
                     instrument_id: instrument_id.clone(),
                     size: -position,
+                    signal: Signal {
+                        hold_utility: Some(utility_of_doing_nothing),
+                        ..Signal::default()
+                    },
                 })
-            }
+            };
+            Ok(order)
         }
     }
 }
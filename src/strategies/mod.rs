@@ -1,3 +1,6 @@
+mod baselines;
 mod basic;
+mod opening_policy;
 
+pub use baselines::{new_baseline, BuyAndHold, RandomEntry, Threshold};
 pub use basic::BasicStrategy;
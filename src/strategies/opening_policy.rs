@@ -0,0 +1,77 @@
+use rand::Rng;
+
+use crate::config::OpeningPolicy;
+use crate::model::ModelSide;
+
+impl OpeningPolicy {
+    /// Side to open given the utilities of opening long and short, if any is positive.
+    pub fn decide(
+        &self,
+        long_utility: f64,
+        short_utility: f64,
+        rng: &mut impl Rng,
+    ) -> Option<ModelSide> {
+        match (long_utility > 0.0, short_utility > 0.0) {
+            (false, false) => None,
+            (true, false) => Some(ModelSide::Long),
+            (false, true) => Some(ModelSide::Short),
+            (true, true) => {
+                let long_probability: f64 = match *self {
+                    OpeningPolicy::Argmax => {
+                        return Some(if long_utility >= short_utility {
+                            ModelSide::Long
+                        } else {
+                            ModelSide::Short
+                        })
+                    }
+                    OpeningPolicy::Proportional => long_utility / (long_utility + short_utility),
+                    OpeningPolicy::Softmax { temperature } => {
+                        1.0 / (1.0 + ((short_utility - long_utility) / temperature).exp())
+                    }
+                };
+                Some(if rng.gen::<f64>() < long_probability {
+                    ModelSide::Long
+                } else {
+                    ModelSide::Short
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_decide() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let policies = [
+            OpeningPolicy::Argmax,
+            OpeningPolicy::Proportional,
+            OpeningPolicy::Softmax { temperature: 1.0 },
+        ];
+        for policy in policies {
+            assert_eq!(policy.decide(-1.0, 0.0, &mut rng), None);
+            assert_eq!(policy.decide(-1.0, 2.0, &mut rng), Some(ModelSide::Short));
+            assert_eq!(policy.decide(1.0, -2.0, &mut rng), Some(ModelSide::Long));
+        }
+        assert_eq!(
+            OpeningPolicy::Argmax.decide(2.0, 2.0, &mut rng),
+            Some(ModelSide::Long)
+        );
+
+        // long utility 3 of 4 in total, and e^1 / (e^1 + e^0) = 0.73 with temperature 2
+        let mut share_long = |policy: OpeningPolicy| {
+            (0..10_000)
+                .filter(|_| policy.decide(3.0, 1.0, &mut rng) == Some(ModelSide::Long))
+                .count() as f64
+                / 10_000.0
+        };
+        assert!((share_long(OpeningPolicy::Proportional) - 0.75).abs() < 0.02);
+        let softmax = OpeningPolicy::Softmax { temperature: 2.0 };
+        assert!((share_long(softmax) - 0.731).abs() < 0.02);
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use toml::Value;
+
+use crate::backtest::BacktestReport;
+use crate::config::Config;
+use crate::run::format_run_id;
+
+/// Search over config values, read from a TOML file, e.g.
+///
+/// ```toml
+/// search = "grid"
+/// [grid]
+/// "iteration.utility_penalty_bps" = [0.0, 0.05, 0.1]
+/// "model.kind" = ["gbdt", "mlp"]
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SweepSpec {
+    #[serde(default)]
+    pub search: Search,
+    /// Values of each dotted config key, combined exhaustively by grid search
+    #[serde(default)]
+    pub grid: BTreeMap<String, Vec<Value>>,
+    /// Distribution of each dotted config key, sampled independently by random search
+    #[serde(default)]
+    pub random: BTreeMap<String, Distribution>,
+    /// Number of points drawn by random search
+    #[serde(default = "SweepSpec::default_n_samples")]
+    pub n_samples: usize,
+    #[serde(default)]
+    pub seed: u64,
+    /// Cores shared by the points run in parallel, each using its own
+    /// `iteration.concurrency`; all available cores by default
+    pub cpus: Option<usize>,
+    /// `BacktestReport` field the results are ranked by
+    #[serde(default = "SweepSpec::default_rank_by")]
+    pub rank_by: String,
+    /// Rank lower values first, e.g. for `max_drawdown`
+    #[serde(default)]
+    pub ascending: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Search {
+    #[default]
+    Grid,
+    Random,
+}
+
+/// Distribution of a config value in random search, e.g. `{ log_uniform = [1e4, 1e6] }`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Distribution {
+    Uniform([f64; 2]),
+    LogUniform([f64; 2]),
+    /// Integers within the bounds, inclusive
+    IntUniform([i64; 2]),
+    Choice(Vec<Value>),
+}
+
+/// Config overrides of one configuration of a sweep
+pub type SweepPoint = Vec<(String, Value)>;
+
+impl SweepSpec {
+    fn default_n_samples() -> usize {
+        10
+    }
+
+    fn default_rank_by() -> String {
+        "total_profit".to_string()
+    }
+
+    pub fn read(path: &str) -> anyhow::Result<Self> {
+        let text: String = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read sweep spec {}: {}", path, e))?;
+        let spec: SweepSpec =
+            toml::from_str(&text).map_err(|e| anyhow!("invalid sweep spec {}: {}", path, e))?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        let n_keys: usize = match self.search {
+            Search::Grid => self.grid.len(),
+            Search::Random => self.random.len(),
+        };
+        if n_keys == 0 {
+            return Err(anyhow!("{:?} search has no keys to vary", self.search));
+        }
+        let n_ignored: usize = match self.search {
+            Search::Grid => self.random.len(),
+            Search::Random => self.grid.len(),
+        };
+        if n_ignored > 0 {
+            return Err(anyhow!(
+                "{:?} search would ignore the keys of the other search, set either grid or random",
+                self.search
+            ));
+        }
+        if let Some((key, _)) = self.grid.iter().find(|(_, values)| values.is_empty()) {
+            return Err(anyhow!("grid.{:?} has no values", key));
+        }
+        for (key, distribution) in self.random.iter() {
+            let is_valid: bool = match distribution {
+                Distribution::Uniform([low, high]) => low <= high,
+                Distribution::LogUniform([low, high]) => 0.0 < *low && low <= high,
+                Distribution::IntUniform([low, high]) => low <= high,
+                Distribution::Choice(values) => !values.is_empty(),
+            };
+            if !is_valid {
+                return Err(anyhow!("random.{:?} has an empty or invalid range", key));
+            }
+        }
+        if report_metric(&BacktestReport::new(&[]), &self.rank_by).is_none() {
+            return Err(anyhow!(
+                "rank_by {:?} is not a backtest report field",
+                self.rank_by
+            ));
+        }
+        Ok(())
+    }
+
+    /// Every combination of the grid values, or `n_samples` random draws.
+    pub fn points(&self) -> Vec<SweepPoint> {
+        match self.search {
+            Search::Grid => {
+                let mut points: Vec<SweepPoint> = vec![vec![]];
+                for (key, values) in self.grid.iter() {
+                    points = points
+                        .iter()
+                        .flat_map(|point| {
+                            values.iter().map(move |value| {
+                                let mut point = point.clone();
+                                point.push((key.clone(), value.clone()));
+                                point
+                            })
+                        })
+                        .collect();
+                }
+                points
+            }
+            Search::Random => {
+                let mut rng = StdRng::seed_from_u64(self.seed);
+                (0..self.n_samples)
+                    .map(|_| {
+                        self.random
+                            .iter()
+                            .map(|(key, distribution)| (key.clone(), distribution.sample(&mut rng)))
+                            .collect()
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Orders results best first by `rank_by`, NaNs last.
+    pub fn rank(&self, results: &mut [(SweepPoint, BacktestReport)]) {
+        let key = |report: &BacktestReport| -> f64 {
+            let value: f64 = report_metric(report, &self.rank_by).unwrap_or(f64::NAN);
+            if self.ascending {
+                value
+            } else {
+                -value
+            }
+        };
+        results.sort_by(|(_, a), (_, b)| {
+            let (a, b) = (key(a), key(b));
+            a.is_nan().cmp(&b.is_nan()).then(a.total_cmp(&b))
+        });
+    }
+}
+
+impl Distribution {
+    fn sample(&self, rng: &mut StdRng) -> Value {
+        match self {
+            Distribution::Uniform([low, high]) => {
+                Value::Float(low + (high - low) * rng.gen::<f64>())
+            }
+            Distribution::LogUniform([low, high]) => {
+                Value::Float((low.ln() + (high.ln() - low.ln()) * rng.gen::<f64>()).exp())
+            }
+            Distribution::IntUniform([low, high]) => Value::Integer(rng.gen_range(*low..=*high)),
+            Distribution::Choice(values) => values[rng.gen_range(0..values.len())].clone(),
+        }
+    }
+}
+
+/// Creates `sweep-{YYYYMMDD-HHMMSS}` under `runs_dir`, holding one runs directory per point.
+pub fn create_dir(runs_dir: &str) -> anyhow::Result<PathBuf> {
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?;
+    let base_name: String = format!("sweep-{}", format_run_id(now.as_secs() as i64));
+    let mut name: String = base_name.clone();
+    let mut suffix: usize = 1;
+    while Path::new(runs_dir).join(&name).exists() {
+        suffix += 1;
+        name = format!("{}-{}", base_name, suffix);
+    }
+    let path: PathBuf = Path::new(runs_dir).join(name);
+    std::fs::create_dir_all(&path)
+        .map_err(|e| anyhow!("failed to create sweep directory {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Fails if the backtest of `config` covers bars it trains on, as points would then be
+/// ranked on their in-sample profits.
+pub fn check_out_of_sample(config: &Config) -> anyhow::Result<()> {
+    let (iteration, backtest) = (&config.iteration, &config.backtest);
+    let train = (iteration.offset, iteration.offset + iteration.limit);
+    let test = (backtest.offset, backtest.offset + backtest.limit);
+    if overlap(train, test) {
+        return Err(anyhow!(
+            "training bars [{}, {}) and backtest bars [{}, {}) of the dataset overlap, \
+             set iteration.offset/limit and backtest.offset/limit apart",
+            train.0,
+            train.1,
+            test.0,
+            test.1
+        ));
+    }
+    Ok(())
+}
+
+/// Whether the fractions `[start, end)` of the dataset share bars, up to rounding
+fn overlap(a: (f64, f64), b: (f64, f64)) -> bool {
+    a.0.max(b.0) < a.1.min(b.1) - 1e-9
+}
+
+/// Value of a numeric `BacktestReport` field by name.
+pub fn report_metric(report: &BacktestReport, name: &str) -> Option<f64> {
+    match serde_json::to_value(report).ok()?.get(name)? {
+        serde_json::Value::Null => Some(f64::NAN),
+        value => value.as_f64(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_points() {
+        let spec: SweepSpec = toml::from_str(
+            r#"
+            [grid]
+            "iteration.n_plays" = [100, 200]
+            "model.kind" = ["gbdt", "mlp", "python"]
+            "#,
+        )
+        .unwrap();
+        spec.validate().unwrap();
+        let points = spec.points();
+        assert_eq!(points.len(), 6);
+        assert_eq!(
+            points[1][0],
+            ("iteration.n_plays".to_string(), Value::Integer(100))
+        );
+        assert_eq!(points[1][1].1, Value::String("mlp".to_string()));
+
+        let random: SweepSpec = toml::from_str(
+            r#"
+            search = "random"
+            [random]
+            "iteration.utility_penalty_bps" = { uniform = [0.0, 0.1] }
+            "iteration.max_play_duration_in_bars" = { int_uniform = [10, 20] }
+            "#,
+        )
+        .unwrap();
+        random.validate().unwrap();
+        let points = random.points();
+        assert_eq!(points.len(), 10);
+        assert_eq!(points, random.points());
+        for point in points {
+            let duration = point[0].1.as_integer().unwrap();
+            let penalty = point[1].1.as_float().unwrap();
+            assert!((10..=20).contains(&duration) && (0.0..=0.1).contains(&penalty));
+        }
+
+        let both = SweepSpec {
+            grid: spec.grid,
+            ..random
+        };
+        assert!(both.validate().is_err());
+    }
+
+    #[test]
+    fn test_overlap() {
+        assert!(!overlap((0.0, 0.75), (0.75, 1.0)));
+        assert!(!overlap((0.25, 0.75), (0.0, 0.25)));
+        assert!(!overlap((0.0, 0.7), (0.7 + 1e-12, 1.0)));
+        assert!(overlap((0.0, 1.0), (0.75, 1.0)));
+        assert!(overlap((0.5, 1.0), (0.0, 0.6)));
+    }
+}
@@ -0,0 +1,34 @@
+//! Points and helpers shared by the unit tests.
+
+use std::path::PathBuf;
+
+use crate::dtypes::Point;
+
+/// Point without features, for tests of trading alone.
+#[derive(Debug, Clone, Point)]
+#[repr(C)]
+pub struct NoFeatures {}
+
+/// Point of a single feature `x`.
+#[derive(Debug, Clone, Point)]
+#[repr(C)]
+pub struct XPoint {
+    pub x: f64,
+}
+
+/// Point of a feature `x` and a feature `noise` that models should ignore.
+#[derive(Debug, Clone, Point)]
+#[repr(C)]
+pub struct XNoisePoint {
+    pub x: f64,
+    pub noise: f64,
+}
+
+/// Empty directory `cfr_<name>_<process id>` under the system temp dir,
+/// which the test removes when done.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir: PathBuf = std::env::temp_dir().join(format!("cfr_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
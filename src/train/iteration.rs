@@ -1,67 +1,107 @@
-use rand::prelude::ThreadRng;
-use rand::Rng;
-use std::sync::{Arc, RwLock, RwLockWriteGuard};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::sync::{Arc, RwLock};
 
-use crate::config::IterationConfig;
+use crate::config::{IterationConfig, ModelConfig, OpeningPolicy};
 use crate::dtypes::{DatasetRef, Point, Sample, Utility};
-use crate::model::{Model, ModelSet, ModelSetRef, ModelType};
+use crate::model::{
+    Diagnostics, FitMetrics, Model, ModelAction, ModelSet, ModelSetRef, ModelSide, ModelType,
+    TrainingInfo,
+};
+use crate::run::{IterationMetrics, ModelMetrics};
 use crate::train::inferrer::Inferrer;
 use crate::train::iteration_stat::IterationStat;
 use crate::train::play::Play;
+use crate::utils::dataset_hash;
+
+/// Start bars drawn for a closing play before giving up on the opening policy
+const MAX_POLICY_STARTS: usize = 50;
 
 /// Runs plays to collect training data and trains models.
 pub struct Iteration<T: Point> {
     iteration_index: usize,
     dataset: DatasetRef<T>,
+    dataset_hash: String,
     config: IterationConfig,
     input_model_set: ModelSetRef<T>,
     output_model_set: ModelSetRef<T>,
     stat: Arc<IterationStat>,
+    /// Filled by `run`, indexed by model type
+    training: RwLock<Vec<TrainingInfo>>,
 }
 
 impl<T: Point> Iteration<T> {
-    pub fn new(iteration_index: usize, dataset: DatasetRef<T>, config: IterationConfig) -> Self {
-        let input_model_set: ModelSetRef<T> =
-            ModelSet::new(iteration_index - 1, &config.output_dir);
-        input_model_set.load_model_params();
-        let output_model_set = ModelSet::new(iteration_index, &config.output_dir);
+    pub fn new(
+        iteration_index: usize,
+        dataset: DatasetRef<T>,
+        config: IterationConfig,
+        model_config: &ModelConfig,
+    ) -> anyhow::Result<Self> {
+        let features: &[String] = &dataset.feature_names;
+        let hash: String = dataset_hash(&dataset);
+        let input_model_set: ModelSetRef<T> = ModelSet::new(
+            iteration_index - 1,
+            &config.output_dir,
+            model_config,
+            features,
+            Some(&hash),
+        )?;
+        input_model_set.load_model_params()?;
+        let output_model_set = ModelSet::new(
+            iteration_index,
+            &config.output_dir,
+            model_config,
+            features,
+            Some(&hash),
+        )?;
         let stat = Arc::new(IterationStat::new());
-        Self {
+        Ok(Self {
             iteration_index,
+            dataset_hash: hash,
             dataset,
             config,
             input_model_set,
             output_model_set,
             stat,
-        }
+            training: RwLock::new(Vec::new()),
+        })
     }
 
-    pub fn run(&self) {
-        let samples: Vec<Vec<Sample<T>>> =
-            ModelType::all().into_iter().map(|_| Vec::new()).collect();
-        let samples = Arc::new(RwLock::new(samples));
-        let join_handles = (0..self.config.concurrency)
-            .map(|_| {
+    pub fn run(&self) -> anyhow::Result<()> {
+        // fixed shares of the plays and merging in worker order keep seeded runs reproducible
+        let n_workers: usize = self.config.concurrency as usize;
+        let n_plays: usize = self.config.n_plays as usize;
+        let join_handles = (0..n_workers)
+            .map(|worker_index| {
                 let config = self.config.clone();
-                let samples2 = samples.clone();
                 let dataset = self.dataset.clone();
                 let models = self.input_model_set.clone();
-                let n_plays = self.config.n_plays as usize; // / self.config.concurrency as usize;
+                let n_worker_plays: usize =
+                    n_plays / n_workers + usize::from(worker_index < n_plays % n_workers);
                 let stat = self.stat.clone();
+                let rng: StdRng = new_rng(self.config.seed, worker_index as u64);
                 std::thread::spawn(move || {
-                    run_plays_sequentially(config, samples2, dataset, models, n_plays, stat)
+                    run_plays_sequentially(config, dataset, models, n_worker_plays, stat, rng)
                 })
             })
             .collect::<Vec<_>>();
-        join_handles
-            .into_iter()
-            .for_each(|handle| handle.join().unwrap());
+        let mut samples: Vec<Vec<Sample<T>>> =
+            ModelType::all().into_iter().map(|_| Vec::new()).collect();
+        for handle in join_handles {
+            let worker_samples: Vec<Vec<Sample<T>>> = handle.join().unwrap()?;
+            for (samples, mut worker_samples) in samples.iter_mut().zip(worker_samples) {
+                samples.append(&mut worker_samples);
+            }
+        }
         println!("training");
-        self.train_models(samples);
-        self.save_models();
+        let training: Vec<TrainingInfo> = self.train_models(samples)?;
+        self.output_model_set.save_model_params(&training)?;
+        *self.training.write().unwrap() = training;
+        Ok(())
     }
 
-    pub fn summary(&self) -> String {
+    pub fn summary(&self) -> anyhow::Result<String> {
         let mut out = String::new();
         out.push_str(&format!(
             "mean play len: {}\n",
@@ -72,122 +112,169 @@ impl<T: Point> Iteration<T> {
             self.stat.mean_prediction(),
         ));
         for model_type in ModelType::all() {
-            let model: &Box<dyn Model<T>> = self.output_model_set.model(model_type);
-            let params = model.params();
+            let model: &dyn Model<T> = self.output_model_set.model(model_type);
+            let params = model.params()?;
             out.push_str(&format!("{}: {}\n", model_type, params));
         }
-        out
+        for (model_type, training) in ModelType::all()
+            .iter()
+            .zip(self.training.read().unwrap().iter())
+        {
+            if let Some(diagnostics) = &training.diagnostics {
+                out.push_str(&format!(
+                    "{} in-sample: {}\n",
+                    model_type, diagnostics.in_sample
+                ));
+                if let Some(holdout) = &diagnostics.holdout {
+                    out.push_str(&format!("{} holdout: {}\n", model_type, holdout));
+                }
+            }
+        }
+        Ok(out)
     }
 
-    fn train_models(&self, samples: Arc<RwLock<Vec<Vec<Sample<T>>>>>) {
-        if samples.read().unwrap().is_empty() {
-            return;
+    /// Play statistics and training details per model, recorded in the run manifest.
+    pub fn metrics(&self) -> IterationMetrics {
+        IterationMetrics {
+            iteration: self.iteration_index,
+            n_plays: self.stat.n_plays(),
+            mean_play_length: self.stat.mean_play_length(),
+            mean_prediction: self.stat.mean_prediction(),
+            models: ModelType::all()
+                .iter()
+                .zip(self.training.read().unwrap().iter())
+                .map(|(model_type, training)| ModelMetrics {
+                    model_type: model_type.to_string(),
+                    n_samples: training.n_samples,
+                    training_secs: training.training_secs,
+                    diagnostics: training.diagnostics.clone(),
+                })
+                .collect(),
         }
+    }
+
+    /// Trains each model on its samples except a random `holdout_fraction` of them.
+    /// Returns training details and fit diagnostics per model type,
+    /// recorded in the saved model files.
+    fn train_models(&self, mut samples: Vec<Vec<Sample<T>>>) -> anyhow::Result<Vec<TrainingInfo>> {
+        let mut training: Vec<TrainingInfo> = ModelType::all()
+            .into_iter()
+            .map(|_| TrainingInfo {
+                dataset_hash: self.dataset_hash.clone(),
+                ..TrainingInfo::default()
+            })
+            .collect();
+        let mut rng: StdRng = new_rng(self.config.seed, self.config.concurrency);
         for model_type in ModelType::all() {
-            let model: &Box<dyn Model<T>> = self.output_model_set.model(model_type);
+            let model: &dyn Model<T> = self.output_model_set.model(model_type);
             let model_index: usize = model_type.into();
-            let modelwise_samples = &samples.read().unwrap()[model_index];
+            let modelwise_samples = &mut samples[model_index];
             if modelwise_samples.is_empty() {
                 continue;
             }
-            model.train(modelwise_samples);
+            let n_holdout: usize =
+                (modelwise_samples.len() as f64 * self.config.holdout_fraction) as usize;
+            if n_holdout > 0 {
+                modelwise_samples.shuffle(&mut rng);
+            }
+            let (holdout_samples, train_samples) = modelwise_samples.split_at(n_holdout);
+            let start = std::time::Instant::now();
+            model
+                .train(train_samples)
+                .map_err(|e| e.context(format!("failed to train {}", model_type)))?;
+            training[model_index].n_samples = train_samples.len();
+            training[model_index].training_secs = start.elapsed().as_secs_f64();
+            let holdout = if holdout_samples.is_empty() {
+                None
+            } else {
+                Some(fit_metrics(model, holdout_samples)?)
+            };
+            training[model_index].diagnostics = Some(Diagnostics {
+                in_sample: fit_metrics(model, train_samples)?,
+                holdout,
+            });
         }
+        Ok(training)
     }
+}
 
-    fn save_models(&self) {
-        std::fs::create_dir_all(&self.config.output_dir).expect("failed to create directory");
-        for model_type in ModelType::all() {
-            let path: String = format!(
-                "{}/{}_{}.json",
-                &self.config.output_dir, self.iteration_index, model_type
-            );
-            let model: &Box<dyn Model<T>> = self.output_model_set.model(model_type);
-            model.save(&path).unwrap();
-        }
+fn fit_metrics<T: Point>(
+    model: &dyn Model<T>,
+    samples: &[Sample<T>],
+) -> anyhow::Result<FitMetrics> {
+    let points: Vec<T> = samples.iter().map(|s| s.point.clone()).collect();
+    let predicted: Vec<f64> = model.infer(&points)?.iter().map(|u| u.0).collect();
+    let realized: Vec<f64> = samples.iter().map(|s| s.utility.0).collect();
+    Ok(FitMetrics::new(&predicted, &realized))
+}
+
+/// Random number generator of one stream of the iteration, derived from `seed` if set.
+fn new_rng(seed: Option<u64>, stream: u64) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed.wrapping_add(stream)),
+        None => StdRng::from_entropy(),
     }
 }
 
-#[allow(dead_code)]
-fn run_plays<T: Point>(
-    config: IterationConfig,
-    samples: Arc<RwLock<Vec<Vec<Sample<T>>>>>,
-    dataset: DatasetRef<T>,
-    models: ModelSetRef<T>,
-    n_plays: usize,
-    stat: Arc<IterationStat>,
-) {
-    let mut inferrer: Inferrer<T> = Inferrer::new(dataset.clone(), models.clone(), n_plays);
-    let mut plays: Vec<Play<T>> = ModelType::all()
-        .iter()
-        .flat_map(|trained_model_type| {
-            (0..n_plays).map(|_| Play::new(&config, dataset.clone(), trained_model_type.clone()))
-        })
-        .collect();
-    let mut all_finished: bool;
-    loop {
-        all_finished = true;
-        inferrer.clear();
-        for (play_index, play) in plays.iter_mut().enumerate() {
-            if play.is_finished() {
-                continue;
-            }
-            match play.advance_to_inference() {
-                Some(request) => {
-                    // println!("request: {:?}", request);
-                    all_finished = false;
-                    inferrer.put_request(play_index, request.model_type, request.bar_index);
-                }
-                None => {
-                    // println!("none!");
-                }
+/// Redraws the start of a closing play until the opening policy opens its side there,
+/// so that closing models learn from the positions the strategy takes. Keeps the last
+/// draw if the policy does not open that side within `MAX_POLICY_STARTS` draws.
+/// `opening_utilities` returns the long and short opening utilities at a bar.
+fn start_on_policy<T: Point>(
+    config: &IterationConfig,
+    dataset: &DatasetRef<T>,
+    mut opening_utilities: impl FnMut(usize) -> anyhow::Result<(Utility, Utility)>,
+    policy: OpeningPolicy,
+    mut play: Play<T>,
+    rng: &mut impl Rng,
+) -> anyhow::Result<Play<T>> {
+    let side: ModelSide = play.trained_model_type().side;
+    for _ in 0..MAX_POLICY_STARTS {
+        let index: usize = play.start_bar_index();
+        if index < dataset.len() && dataset[index].point.is_finite() {
+            let (long_utility, short_utility) = opening_utilities(index)?;
+            if policy.decide(long_utility.0, short_utility.0, rng) == Some(side) {
+                break;
             }
         }
-        if all_finished {
-            break;
-        }
-        inferrer
-            .fulfill_all_requests()
-            .iter()
-            .for_each(|inference| {
-                let play: &mut Play<T> = &mut plays[inference.play_index];
-                stat.update_predictions(inference.prediction.0);
-                play.advance_with_inference(inference.prediction);
-            });
-    }
-    for play in plays.iter() {
-        if !dataset[play.start_bar_index()].point.is_finite() {
-            continue;
-        }
-        let point: T = dataset[play.start_bar_index()].point.clone();
-        stat.update_play_lengths(play.len());
-        let model_type: ModelType = play.trained_model_type();
-        let model_index: usize = model_type.into();
-        let utility: Utility = play.utility();
-        let sample: Sample<T> = Sample { point, utility };
-        samples.write().unwrap()[model_index].push(sample);
+        play = Play::new(config, dataset.clone(), play.trained_model_type(), rng);
     }
+    Ok(play)
 }
 
+/// Runs `n_plays` plays one after another, returning their samples by model type.
 fn run_plays_sequentially<T: Point>(
     config: IterationConfig,
-    samples: Arc<RwLock<Vec<Vec<Sample<T>>>>>,
     dataset: DatasetRef<T>,
     models: ModelSetRef<T>,
     n_plays: usize,
     stat: Arc<IterationStat>,
-) {
-    let inferrer: Inferrer<T> = Inferrer::new(dataset.clone(), models.clone(), n_plays);
+    mut rng: StdRng,
+) -> anyhow::Result<Vec<Vec<Sample<T>>>> {
+    let inferrer: Inferrer<T> = Inferrer::new(dataset.clone(), models);
     let mut local_samples: Vec<Vec<Sample<T>>> =
         ModelType::all().into_iter().map(|_| Vec::new()).collect();
-    let mut rng: ThreadRng = rand::thread_rng();
+    let opening = |side: ModelSide| ModelType {
+        side,
+        action: ModelAction::Opening,
+    };
+    let opening_utilities = |index: usize| -> anyhow::Result<(Utility, Utility)> {
+        Ok((
+            inferrer.infer(opening(ModelSide::Long), index)?,
+            inferrer.infer(opening(ModelSide::Short), index)?,
+        ))
+    };
 
-    loop {
-        if stat.n_plays() >= n_plays {
-            break;
-        }
+    for _ in 0..n_plays {
         let trained_model_type =
             ModelType::try_from(rng.gen_range(0..ModelType::N_VARIANTS)).unwrap();
-        let mut play = Play::new(&config, dataset.clone(), trained_model_type.clone());
+        let mut play = Play::new(&config, dataset.clone(), trained_model_type, &mut rng);
+        if let Some(policy) = config.opening_policy {
+            if trained_model_type.action == ModelAction::Closing {
+                play =
+                    start_on_policy(&config, &dataset, opening_utilities, policy, play, &mut rng)?;
+            }
+        }
 
         loop {
             if play.is_finished() {
@@ -195,7 +282,7 @@ fn run_plays_sequentially<T: Point>(
             }
             match play.advance_to_inference() {
                 Some(request) => {
-                    let utility = inferrer.infer(request.model_type, request.bar_index);
+                    let utility = inferrer.infer(request.model_type, request.bar_index)?;
                     stat.update_predictions(utility.0);
                     play.advance_with_inference(utility);
                 }
@@ -205,16 +292,86 @@ fn run_plays_sequentially<T: Point>(
             }
         }
 
+        // no samples of NaN features, where the start of a play runs past the end
+        let point: T = match dataset.get(play.start_bar_index()) {
+            Some(bar) if bar.point.is_finite() => bar.point.clone(),
+            _ => continue,
+        };
         stat.update_play_lengths(play.len());
-        let point: T = dataset[play.start_bar_index()].point.clone();
         let model_index: usize = play.trained_model_type().into();
         let utility: Utility = play.utility();
         let sample: Sample<T> = Sample { point, utility };
         local_samples[model_index].push(sample);
     }
-    let mut locked_samples: RwLockWriteGuard<Vec<Vec<Sample<T>>>> = samples.write().unwrap();
-    ModelType::all().into_iter().for_each(|model_type| {
-        let model_index: usize = model_type.into();
-        locked_samples[model_index].extend(local_samples[model_index].drain(..));
-    });
+    Ok(local_samples)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dtypes::{Bar, Price};
+    use crate::test_support::XPoint as P;
+
+    #[test]
+    fn test_start_on_policy() {
+        let dataset: DatasetRef<P> = Arc::new(
+            (0..20)
+                .map(|i| Bar {
+                    timestamp: i,
+                    mid_price: Price(100.0),
+                    point: P { x: 0.0 },
+                })
+                .collect(),
+        );
+        // both sides worth opening everywhere, long the better one only at bar 7
+        let opening_utilities = |index: usize| -> anyhow::Result<(Utility, Utility)> {
+            let long_utility: f64 = if index == 7 { 10.0 } else { 1.0 };
+            Ok((Utility(long_utility), Utility(5.0)))
+        };
+        let config = IterationConfig::default();
+        let closing_long = ModelType {
+            side: ModelSide::Long,
+            action: ModelAction::Closing,
+        };
+        let policy = OpeningPolicy::Softmax { temperature: 0.1 };
+        let mut rng = StdRng::seed_from_u64(1);
+        let play = Play::new(&config, dataset.clone(), closing_long, &mut rng);
+        let first_start: usize = play.start_bar_index();
+        let play =
+            start_on_policy(&config, &dataset, opening_utilities, policy, play, &mut rng).unwrap();
+        assert_ne!(first_start, 7);
+        assert_eq!(play.start_bar_index(), 7);
+    }
+
+    #[test]
+    fn test_samples_have_finite_points() {
+        // features are NaN on every other bar and on the last 50 bars
+        let dataset: DatasetRef<P> = Arc::new(
+            (0..200)
+                .map(|i| Bar {
+                    timestamp: i,
+                    mid_price: Price(100.0 + (i % 7) as f64),
+                    point: P {
+                        x: if i % 2 == 0 || i >= 150 {
+                            f64::NAN
+                        } else {
+                            i as f64
+                        },
+                    },
+                })
+                .collect(),
+        );
+        let config = IterationConfig {
+            max_play_duration_in_bars: 10,
+            ..IterationConfig::default()
+        };
+        let features: &[String] = &dataset.feature_names;
+        let models = ModelSet::new(0, "", &ModelConfig::default(), features, None).unwrap();
+        let stat = Arc::new(IterationStat::new());
+        let rng = StdRng::seed_from_u64(1);
+        let samples = run_plays_sequentially(config, dataset, models, 200, stat, rng).unwrap();
+        let n_samples: usize = samples.iter().map(Vec::len).sum();
+        assert!(n_samples > 0 && n_samples < 200);
+        assert!(samples.iter().flatten().all(|s| s.point.is_finite()));
+    }
 }
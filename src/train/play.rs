@@ -1,5 +1,4 @@
 use crate::config::IterationConfig;
-use rand::prelude::ThreadRng;
 use rand::Rng;
 
 use crate::dtypes::{Bar, Utility};
@@ -25,8 +24,8 @@ impl<T: Point> Play<T> {
         config: &IterationConfig,
         dataset: DatasetRef<T>,
         trained_model_type: ModelType,
+        rng: &mut impl Rng,
     ) -> Self {
-        let mut rng: ThreadRng = rand::thread_rng();
         let mut start_index: usize = rng.gen_range(0..dataset.len() - 10);
         loop {
             if start_index >= dataset.len() {
@@ -0,0 +1,43 @@
+use crate::dtypes::{Bar, Point};
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// 64-bit FNV-1a hasher for checksums and content ids.
+pub struct Fnv1a {
+    hash: u64,
+}
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self { hash: FNV_OFFSET }
+    }
+}
+
+impl Fnv1a {
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.hash ^= byte as u64;
+            self.hash = self.hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+/// FNV-1a hash of timestamps, prices and features, as a hex string.
+/// Identifies the dataset a model was trained on.
+pub fn dataset_hash<T: Point>(dataset: &[Bar<T>]) -> String {
+    let mut hasher = Fnv1a::default();
+    for bar in dataset {
+        hasher.update(&bar.timestamp.to_le_bytes());
+        hasher.update(&bar.mid_price.0.to_le_bytes());
+        bar.point
+            .as_slice()
+            .iter()
+            .for_each(|x| hasher.update(&x.to_le_bytes()));
+    }
+    format!("{:016x}", hasher.finish())
+}
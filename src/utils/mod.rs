@@ -1,4 +1,8 @@
+mod hash;
 mod path_utils;
 mod regret;
+mod stats;
 
+pub use hash::{dataset_hash, Fnv1a};
 pub use path_utils::canonicalize_path;
+pub use stats::{normal_quantile, quantile};
@@ -0,0 +1,123 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use cfr_proto::datasets::synthetic::{generate, Process, SyntheticConfig, SyntheticPoint};
+use cfr_proto::strategies::BasicStrategy;
+use cfr_proto::{
+    Backtester, DatasetRef, GbdtConfig, InstrumentId, InstrumentSpec, Iteration, IterationConfig,
+    ModelConfig, ModelKind, ModelSet, Position, StrategyConfig,
+};
+
+const FEE: f64 = 0.05; // 5 bps of the start price per side
+const N_ITERATIONS: usize = 4;
+
+/// Trains on the first 70% of the bars and returns the backtest profit on the rest,
+/// marking the position open at the end to market.
+fn out_of_sample_profit(name: &str, process: Process) -> f64 {
+    let dataset: DatasetRef<SyntheticPoint> = generate(&SyntheticConfig {
+        process,
+        n_bars: 20_000,
+        start_price: 100.0,
+        bar_secs: 60,
+        seed: 7,
+    });
+    let split: usize = dataset.len() * 7 / 10;
+    let train_set: DatasetRef<SyntheticPoint> = Arc::new(dataset.range(0..split));
+    let test_set: DatasetRef<SyntheticPoint> = Arc::new(dataset.range(split..dataset.len()));
+    let output_dir = std::env::temp_dir()
+        .join(format!("cfr_synthetic_{}_{}", name, std::process::id()))
+        .to_str()
+        .unwrap()
+        .to_string();
+    let iteration_config = IterationConfig {
+        n_plays: 4_000,
+        concurrency: 2,
+        output_dir: output_dir.clone(),
+        fee_per_contract_usd: FEE,
+        multiplier: 1.0,
+        utility_penalty_bps: 0.0,
+        max_play_duration_in_bars: 20,
+        offset: 0.0,
+        limit: 1.0,
+        holdout_fraction: 0.0,
+        opening_policy: None,
+        seed: None,
+    };
+    let model_config = ModelConfig {
+        kind: ModelKind::Gbdt,
+        gbdt: GbdtConfig {
+            n_trees: 50,
+            min_samples_leaf: 50,
+            ..GbdtConfig::default()
+        },
+        ..ModelConfig::default()
+    };
+    for iteration_index in 1..=N_ITERATIONS {
+        let iteration: Iteration<SyntheticPoint> = Iteration::new(
+            iteration_index,
+            train_set.clone(),
+            iteration_config.clone(),
+            &model_config,
+        )
+        .unwrap();
+        iteration.run().unwrap();
+    }
+
+    let model_set = ModelSet::new(
+        N_ITERATIONS,
+        &output_dir,
+        &model_config,
+        &dataset.feature_names,
+        None,
+    )
+    .unwrap();
+    model_set
+        .load_model_params_with_close_from_previous_iteration()
+        .unwrap();
+    let instrument_id = InstrumentId {
+        symbol: "SYN".to_string(),
+        index: 0,
+    };
+    let instrument_spec = InstrumentSpec {
+        multiplier: 1.0,
+        fee: FEE,
+        roll_fee: 0.0,
+    };
+    let positions = Rc::new(RefCell::new(vec![Position::new(
+        instrument_id,
+        instrument_spec,
+    )]));
+    let strategy = BasicStrategy::new(positions.clone(), model_set, &StrategyConfig::default());
+    let last_price: f64 = test_set[test_set.len() - 1].mid_price.0;
+    let mut backtester = Backtester::new(positions.clone(), test_set, Box::new(strategy));
+    backtester.run().unwrap();
+    std::fs::remove_dir_all(&output_dir).unwrap();
+    let realized: f64 = backtester.realized_profits().iter().map(|p| p.profit).sum();
+    let unrealized: f64 = positions.borrow()[0].unrealized_profit(last_price);
+    realized + unrealized
+}
+
+#[test]
+fn test_learns_planted_signal() {
+    let profit = out_of_sample_profit(
+        "planted",
+        Process::PlantedSignal {
+            volatility_bps: 10.0,
+            signal_to_noise: 0.5,
+            persistence_bars: 10.0,
+        },
+    );
+    assert!(profit > 0.0, "out-of-sample profit {}", profit);
+}
+
+#[test]
+fn test_does_not_profit_from_noise() {
+    let profit = out_of_sample_profit(
+        "noise",
+        Process::RandomWalk {
+            volatility_bps: 10.0,
+        },
+    );
+    assert!(profit <= 0.0, "out-of-sample profit {}", profit);
+}